@@ -0,0 +1,549 @@
+use std::cmp::Ordering;
+use std::f64::consts::PI;
+
+use num::complex::{Complex, Complex64};
+use num::pow::Pow;
+use num::{BigInt, BigRational, FromPrimitive, Integer, One, Signed, ToPrimitive, Zero};
+
+use crate::data::Number;
+
+impl Number {
+    pub fn complex(value: Complex64) -> Number {
+        if value.im == 0.0 {
+            Number::Real(value.re)
+        } else {
+            Number::Complex(value)
+        }
+    }
+
+    pub fn exact_complex(value: Complex<BigRational>) -> Number {
+        if value.im.is_zero() {
+            Number::rational(value.re)
+        } else {
+            Number::ExactComplex(value)
+        }
+    }
+
+    pub fn rectangular(real: &Number, imag: &Number) -> Number {
+        if imag.is_exact_zero() {
+            real.clone()
+        } else if let (Some(re), Some(im)) = (real.to_rational(), imag.to_rational()) {
+            Number::exact_complex(Complex::new(re, im))
+        } else {
+            Number::complex(Complex64::new(real.to_f64(), imag.to_f64()))
+        }
+    }
+
+    pub fn polar(magnitude: &Number, angle: &Number) -> Number {
+        if angle.is_exact_zero() {
+            magnitude.clone()
+        } else {
+            Number::complex(Complex64::from_polar(magnitude.to_f64(), angle.to_f64()))
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        matches!(self, Number::Integer(_) | Number::Rational(_) | Number::ExactComplex(_))
+    }
+
+    pub fn is_exact_zero(&self) -> bool {
+        matches!(self, Number::Integer(i) if i.is_zero())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        use Number::*;
+
+        match self {
+            Integer(i) => i.is_zero(),
+            Rational(r) => r.is_zero(),
+            Real(r) => *r == 0.0,
+            Complex(c) => c.is_zero(),
+            ExactComplex(c) => c.is_zero(),
+        }
+    }
+
+    pub fn is_real(&self) -> bool {
+        !matches!(self, Number::Complex(_) | Number::ExactComplex(_))
+    }
+
+    pub fn is_rational(&self) -> bool {
+        use Number::*;
+
+        match self {
+            Integer(_) | Rational(_) => true,
+            Real(r) => r.is_finite(),
+            Complex(_) | ExactComplex(_) => false,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        use Number::*;
+
+        match self {
+            Integer(_) => true,
+            Rational(_) | Complex(_) | ExactComplex(_) => false,
+            Real(r) => r.is_finite() && r.fract() == 0.0,
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        use Number::*;
+
+        match self {
+            Integer(i) => i.is_negative(),
+            Rational(r) => r.is_negative(),
+            Real(r) => *r < 0.0,
+            Complex(_) | ExactComplex(_) => false,
+        }
+    }
+
+    /// Converts a real number to the nearest `f64`; complex numbers yield their real part.
+    pub fn to_f64(&self) -> f64 {
+        use Number::*;
+
+        fn overflow(negative: bool) -> f64 {
+            if negative { f64::NEG_INFINITY } else { f64::INFINITY }
+        }
+
+        match self {
+            Integer(i) => i.to_f64().unwrap_or_else(|| overflow(i.is_negative())),
+            Rational(r) => r.to_f64().unwrap_or_else(|| overflow(r.is_negative())),
+            Real(r) => *r,
+            Complex(c) => c.re,
+            ExactComplex(c) => Rational(c.re.clone()).to_f64(),
+        }
+    }
+
+    /// Converts to an exact number, returning `None` for infinities and NaNs, including those
+    /// in either part of a complex number.
+    pub fn to_exact(&self) -> Option<Number> {
+        match self {
+            Number::Real(r) => BigRational::from_float(*r).map(Number::rational),
+            Number::Complex(c) => {
+                let re = BigRational::from_float(c.re)?;
+                let im = BigRational::from_float(c.im)?;
+                Some(Number::exact_complex(Complex::new(re, im)))
+            },
+            _ => Some(self.clone()),
+        }
+    }
+
+    /// Converts an exact real number to a `BigRational`.
+    pub fn to_rational(&self) -> Option<BigRational> {
+        match self {
+            Number::Integer(i) => Some(BigRational::from_integer(i.clone())),
+            Number::Rational(r) => Some(r.clone()),
+            _ => None,
+        }
+    }
+
+    /// Converts an integer, exact or inexact, to a `BigInt`.
+    pub fn to_integer(&self) -> Option<BigInt> {
+        match self {
+            Number::Integer(i) => Some(i.clone()),
+            Number::Real(r) if self.is_integer() => BigInt::from_f64(*r),
+            _ => None,
+        }
+    }
+
+    pub fn to_inexact(&self) -> Number {
+        match self {
+            Number::Complex(_) => self.clone(),
+            Number::ExactComplex(_) => Number::Complex(self.to_complex()),
+            _ => Number::Real(self.to_f64()),
+        }
+    }
+
+    pub fn to_complex(&self) -> Complex64 {
+        match self {
+            Number::Complex(c) => *c,
+            Number::ExactComplex(c) => Complex64::new(self.to_f64(), Number::Rational(c.im.clone()).to_f64()),
+            _ => Complex64::new(self.to_f64(), 0.0),
+        }
+    }
+
+    pub fn neg(&self) -> Number {
+        use Number::*;
+
+        match self {
+            Integer(i) => Integer(-i),
+            Rational(r) => Rational(-r),
+            Real(r) => Real(-r),
+            Complex(c) => Complex(-c),
+            ExactComplex(c) => ExactComplex(-c),
+        }
+    }
+
+    pub fn add(&self, other: &Number) -> Number {
+        use Number::*;
+
+        if let (Integer(a), Integer(b)) = (self, other) {
+            return Integer(a + b);
+        }
+
+        match coerce(self, other) {
+            (Integer(a), Integer(b)) => Integer(a + b),
+            (Rational(a), Rational(b)) => Number::rational(a + b),
+            (Real(a), Real(b)) => Real(a + b),
+            (ExactComplex(a), ExactComplex(b)) => Number::exact_complex(a + b),
+            (a, b) => Number::complex(a.to_complex() + b.to_complex()),
+        }
+    }
+
+    pub fn sub(&self, other: &Number) -> Number {
+        if let (Number::Integer(a), Number::Integer(b)) = (self, other) {
+            return Number::Integer(a - b);
+        }
+
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Number) -> Number {
+        use Number::*;
+
+        if let (Integer(a), Integer(b)) = (self, other) {
+            return Integer(a * b);
+        }
+
+        match coerce(self, other) {
+            (Integer(a), Integer(b)) => Integer(a * b),
+            (Rational(a), Rational(b)) => Number::rational(a * b),
+            (Real(a), Real(b)) => Real(a * b),
+            (ExactComplex(a), ExactComplex(b)) => Number::exact_complex(a * b),
+            (a, b) => Number::complex(a.to_complex() * b.to_complex()),
+        }
+    }
+
+    /// Divides two numbers, returning `None` when dividing by an exact zero.
+    pub fn div(&self, other: &Number) -> Option<Number> {
+        use Number::*;
+
+        if other.is_exact() && other.is_zero() {
+            return None;
+        }
+
+        Some(match coerce(self, other) {
+            (Integer(a), Integer(b)) => Number::rational(BigRational::new(a, b)),
+            (Rational(a), Rational(b)) => Number::rational(a / b),
+            (Real(a), Real(b)) => Real(a / b),
+            (ExactComplex(a), ExactComplex(b)) => Number::exact_complex(a / b),
+            (a, b) => Number::complex(a.to_complex() / b.to_complex()),
+        })
+    }
+
+    pub fn abs(&self) -> Number {
+        use Number::*;
+
+        match self {
+            Integer(i) => Integer(i.abs()),
+            Rational(r) => Rational(r.abs()),
+            Real(r) => Real(r.abs()),
+            Complex(c) => Real(c.norm()),
+            ExactComplex(c) => Number::rational(c.norm_sqr()).sqrt(),
+        }
+    }
+
+    /// Divides two integers, rounding the quotient towards negative infinity. Returns the
+    /// quotient and remainder, or `None` when dividing by zero.
+    pub fn floor_div(&self, other: &Number) -> Option<(Number, Number)> {
+        self.integer_div(other, true)
+    }
+
+    /// Divides two integers, rounding the quotient towards zero. Returns the quotient and
+    /// remainder, or `None` when dividing by zero.
+    pub fn truncate_div(&self, other: &Number) -> Option<(Number, Number)> {
+        self.integer_div(other, false)
+    }
+
+    pub fn gcd(&self, other: &Number) -> Option<Number> {
+        let result = Number::Integer(self.to_integer()?.gcd(&other.to_integer()?));
+        Some(with_exactness(result, self, other))
+    }
+
+    pub fn lcm(&self, other: &Number) -> Option<Number> {
+        let result = Number::Integer(self.to_integer()?.lcm(&other.to_integer()?));
+        Some(with_exactness(result, self, other))
+    }
+
+    /// Returns the smaller of two real numbers, which is inexact if either argument is.
+    pub fn min(&self, other: &Number) -> Number {
+        let result = match self.partial_cmp(other) {
+            Some(Ordering::Greater) => other.clone(),
+            Some(_) => self.clone(),
+            None => Number::Real(f64::NAN),
+        };
+
+        with_exactness(result, self, other)
+    }
+
+    /// Returns the larger of two real numbers, which is inexact if either argument is.
+    pub fn max(&self, other: &Number) -> Number {
+        let result = match self.partial_cmp(other) {
+            Some(Ordering::Less) => other.clone(),
+            Some(_) => self.clone(),
+            None => Number::Real(f64::NAN),
+        };
+
+        with_exactness(result, self, other)
+    }
+
+    /// Raises a number to a power, returning `None` when raising an exact zero to a negative
+    /// power. Exact numbers raised to exact integer powers stay exact.
+    pub fn expt(&self, power: &Number) -> Option<Number> {
+        use Number::*;
+
+        match (self, power) {
+            (Integer(_) | Rational(_), Integer(n)) => {
+                if self.is_exact_zero() && n.is_negative() {
+                    return None;
+                }
+
+                Some(Number::rational(self.to_rational()?.pow(n)))
+            },
+            (ExactComplex(c), Integer(n)) if n.to_i32().is_some() => {
+                Some(Number::exact_complex(c.powi(n.to_i32()?)))
+            },
+            _ if power.is_exact_zero() => Some(Integer(BigInt::one())),
+            _ if !self.is_real() || !power.is_real() => Some(Number::complex(self.to_complex().powc(power.to_complex()))),
+            _ if self.is_negative() && !power.is_integer() => {
+                Some(Number::complex(self.to_complex().powc(power.to_complex())))
+            },
+            _ => Some(Real(self.to_f64().powf(power.to_f64()))),
+        }
+    }
+
+    /// Computes the integer square root of an exact non-negative integer, along with the
+    /// remainder.
+    pub fn exact_integer_sqrt(&self) -> Option<(Number, Number)> {
+        match self {
+            Number::Integer(i) if !i.is_negative() => {
+                let root = i.sqrt();
+                let rest = i - &root * &root;
+                Some((Number::Integer(root), Number::Integer(rest)))
+            },
+            _ => None,
+        }
+    }
+
+    pub fn floor(&self) -> Number {
+        self.round_with(BigRational::floor, f64::floor)
+    }
+
+    pub fn ceiling(&self) -> Number {
+        self.round_with(BigRational::ceil, f64::ceil)
+    }
+
+    pub fn truncate(&self) -> Number {
+        self.round_with(BigRational::trunc, f64::trunc)
+    }
+
+    /// Rounds to the nearest integer, with ties going to the even integer.
+    pub fn round(&self) -> Number {
+        fn round_even(r: &BigRational) -> BigRational {
+            let floor = r.floor();
+            let half = BigRational::new(BigInt::one(), BigInt::from(2));
+
+            match (r - &floor).cmp(&half) {
+                Ordering::Greater => floor + BigInt::one(),
+                Ordering::Equal if floor.to_integer().is_odd() => floor + BigInt::one(),
+                _ => floor,
+            }
+        }
+
+        self.round_with(round_even, f64::round_ties_even)
+    }
+
+    pub fn real_part(&self) -> Number {
+        match self {
+            Number::Complex(c) => Number::Real(c.re),
+            Number::ExactComplex(c) => Number::rational(c.re.clone()),
+            _ => self.clone(),
+        }
+    }
+
+    pub fn imag_part(&self) -> Number {
+        match self {
+            Number::Complex(c) => Number::Real(c.im),
+            Number::ExactComplex(c) => Number::rational(c.im.clone()),
+            _ => Number::Integer(BigInt::zero()),
+        }
+    }
+
+    pub fn magnitude(&self) -> Number {
+        self.abs()
+    }
+
+    pub fn angle(&self) -> Number {
+        use Number::*;
+
+        match self {
+            _ if !self.is_real() => Real(self.to_complex().arg()),
+            _ if self.is_negative() => Real(PI),
+            Real(_) => Real(0.0),
+            _ => Integer(BigInt::zero()),
+        }
+    }
+
+    pub fn exp(&self) -> Number {
+        match self {
+            _ if !self.is_real() => Number::complex(self.to_complex().exp()),
+            _ => Number::Real(self.to_f64().exp()),
+        }
+    }
+
+    pub fn log(&self) -> Number {
+        match self {
+            _ if !self.is_real() => Number::complex(self.to_complex().ln()),
+            _ if self.is_negative() => Number::complex(self.to_complex().ln()),
+            _ => Number::Real(self.to_f64().ln()),
+        }
+    }
+
+    pub fn sqrt(&self) -> Number {
+        use Number::*;
+
+        fn exact_sqrt(i: &BigInt) -> Option<BigInt> {
+            let root = i.sqrt();
+            if &root * &root == *i { Some(root) } else { None }
+        }
+
+        match self {
+            Integer(i) if !i.is_negative() => {
+                if let Some(root) = exact_sqrt(i) {
+                    return Integer(root);
+                }
+            },
+            Rational(r) if !r.is_negative() => {
+                if let (Some(n), Some(d)) = (exact_sqrt(r.numer()), exact_sqrt(r.denom())) {
+                    return Number::rational(BigRational::new(n, d));
+                }
+            },
+            _ => {},
+        }
+
+        match self {
+            _ if !self.is_real() => Number::complex(self.to_complex().sqrt()),
+            _ if self.is_negative() => Number::complex(Complex64::new(0.0, (-self.to_f64()).sqrt())),
+            _ => Real(self.to_f64().sqrt()),
+        }
+    }
+
+    pub fn sin(&self) -> Number {
+        match self {
+            _ if !self.is_real() => Number::complex(self.to_complex().sin()),
+            _ => Number::Real(self.to_f64().sin()),
+        }
+    }
+
+    pub fn cos(&self) -> Number {
+        match self {
+            _ if !self.is_real() => Number::complex(self.to_complex().cos()),
+            _ => Number::Real(self.to_f64().cos()),
+        }
+    }
+
+    pub fn tan(&self) -> Number {
+        match self {
+            _ if !self.is_real() => Number::complex(self.to_complex().tan()),
+            _ => Number::Real(self.to_f64().tan()),
+        }
+    }
+
+    pub fn asin(&self) -> Number {
+        match self {
+            _ if self.is_real() && self.to_f64().abs() <= 1.0 => Number::Real(self.to_f64().asin()),
+            _ => Number::complex(self.to_complex().asin()),
+        }
+    }
+
+    pub fn acos(&self) -> Number {
+        match self {
+            _ if self.is_real() && self.to_f64().abs() <= 1.0 => Number::Real(self.to_f64().acos()),
+            _ => Number::complex(self.to_complex().acos()),
+        }
+    }
+
+    pub fn atan(&self) -> Number {
+        match self {
+            _ if !self.is_real() => Number::complex(self.to_complex().atan()),
+            _ => Number::Real(self.to_f64().atan()),
+        }
+    }
+
+    /// Computes the angle of the point `(x, y)`; both numbers must be real.
+    pub fn atan2(&self, x: &Number) -> Number {
+        Number::Real(self.to_f64().atan2(x.to_f64()))
+    }
+
+    fn integer_div(&self, other: &Number, floor: bool) -> Option<(Number, Number)> {
+        use Number::*;
+
+        if other.is_zero() {
+            return None;
+        }
+
+        match (self, other) {
+            (Integer(a), Integer(b)) => {
+                let (q, r) = if floor { a.div_mod_floor(b) } else { a.div_rem(b) };
+                Some((Integer(q), Integer(r)))
+            },
+            _ => {
+                let (a, b) = (self.to_f64(), other.to_f64());
+                let mut r = a % b;
+                if floor && r != 0.0 && (r < 0.0) != (b < 0.0) {
+                    r += b;
+                }
+                Some((Real(((a - r) / b).round()), Real(r)))
+            },
+        }
+    }
+
+    fn round_with(&self, exact: fn(&BigRational) -> BigRational, inexact: fn(f64) -> f64) -> Number {
+        match self {
+            Number::Rational(r) => Number::rational(exact(r)),
+            Number::Real(r) => Number::Real(inexact(*r)),
+            _ => self.clone(),
+        }
+    }
+
+    fn level(&self) -> u8 {
+        use Number::*;
+
+        match self {
+            Integer(_) => 0,
+            Rational(_) => 1,
+            Real(_) => 2,
+            ExactComplex(_) => 3,
+            Complex(_) => 4,
+        }
+    }
+
+    fn promote(&self, level: u8) -> Number {
+        use Number::*;
+
+        match (self, level) {
+            _ if self.level() == level => self.clone(),
+            (Integer(i), 1) => Rational(BigRational::from_integer(i.clone())),
+            (_, 2) => Real(self.to_f64()),
+            (_, 3) => ExactComplex(self.to_rational().unwrap().into()),
+            _ => Complex(self.to_complex()),
+        }
+    }
+}
+
+fn with_exactness(result: Number, a: &Number, b: &Number) -> Number {
+    if a.is_exact() && b.is_exact() {
+        result
+    } else {
+        result.to_inexact()
+    }
+}
+
+fn coerce(a: &Number, b: &Number) -> (Number, Number) {
+    let level = match a.level().max(b.level()) {
+        // An exact complex number mixed with an inexact real becomes an inexact complex number.
+        3 if !a.is_exact() || !b.is_exact() => 4,
+        level => level,
+    };
+    (a.promote(level), b.promote(level))
+}
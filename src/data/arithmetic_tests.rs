@@ -0,0 +1,141 @@
+#![cfg(test)]
+
+use test_case::test_case;
+
+use crate::data::Number;
+use crate::data::test_helpers::*;
+
+#[test_case(integer(2), integer(3) => integer(5); "integers")]
+#[test_case(integer(1), rational(1, 2) => rational(3, 2); "integer and rational")]
+#[test_case(rational(1, 2), rational(1, 2) => integer(1); "rationals to integer")]
+#[test_case(rational(1, 2), real(0.25) => real(0.75); "rational and real")]
+#[test_case(real(1.0), complex(1.0, 2.0) => complex(2.0, 2.0); "real and complex")]
+#[test_case(complex(1.0, 2.0), complex(1.0, -2.0) => real(2.0); "complex to real")]
+fn add(a: Number, b: Number) -> Number {
+    a.add(&b)
+}
+
+#[test_case(integer(2), integer(3) => integer(-1); "integers")]
+#[test_case(rational(1, 3), integer(1) => rational(-2, 3); "rational and integer")]
+fn sub(a: Number, b: Number) -> Number {
+    a.sub(&b)
+}
+
+#[test_case(integer(4), rational(1, 4) => integer(1); "integer and rational")]
+#[test_case(complex(0.0, 1.0), complex(0.0, 1.0) => real(-1.0); "i squared")]
+#[test_case(integer(3), real(0.5) => real(1.5); "integer and real")]
+fn mul(a: Number, b: Number) -> Number {
+    a.mul(&b)
+}
+
+#[test_case(integer(1), integer(3) => Some(rational(1, 3)); "integers to rational")]
+#[test_case(integer(6), integer(3) => Some(integer(2)); "integers to integer")]
+#[test_case(real(1.0), integer(4) => Some(real(0.25)); "real and integer")]
+#[test_case(integer(1), integer(0) => None; "exact division by zero")]
+#[test_case(integer(1), real(0.0) => Some(real(f64::INFINITY)); "inexact division by zero")]
+fn div(a: Number, b: Number) -> Option<Number> {
+    a.div(&b)
+}
+
+#[test_case(integer(16) => integer(4); "exact square")]
+#[test_case(rational(4, 9) => rational(2, 3); "exact rational square")]
+#[test_case(integer(2) => real(2f64.sqrt()); "inexact root")]
+#[test_case(integer(-4) => complex(0.0, 2.0); "negative number")]
+#[test_case(complex(-4.0, 0.0) => complex(0.0, 2.0); "complex number")]
+fn sqrt(value: Number) -> Number {
+    value.sqrt()
+}
+
+#[test_case(integer(-1) => complex(0.0, std::f64::consts::PI); "negative logarithm")]
+#[test_case(real(1.0) => real(0.0); "real logarithm")]
+fn log(value: Number) -> Number {
+    value.log()
+}
+
+#[test_case(integer(2) => real(std::f64::consts::FRAC_PI_2); "out of domain real")]
+fn asin_real_part(value: Number) -> Number {
+    value.asin().real_part()
+}
+
+#[test_case(integer(3), integer(4) => complex(3.0, 4.0); "inexact complex")]
+#[test_case(integer(3), integer(0) => integer(3); "exact zero imaginary part")]
+fn rectangular(re: Number, im: Number) -> Number {
+    Number::rectangular(&re, &im)
+}
+
+#[test_case(complex(3.0, 4.0) => real(5.0); "complex magnitude")]
+#[test_case(rational(-1, 2) => rational(1, 2); "exact magnitude")]
+fn magnitude(value: Number) -> Number {
+    value.magnitude()
+}
+
+#[test_case(integer(7), integer(2) => Some(("3".into(), "1".into())); "positive integers")]
+#[test_case(integer(-7), integer(2) => Some(("-4".into(), "1".into())); "negative dividend")]
+#[test_case(integer(7), integer(-2) => Some(("-4".into(), "-1".into())); "negative divisor")]
+#[test_case(real(-7.0), integer(2) => Some(("-4.0".into(), "1.0".into())); "inexact dividend")]
+#[test_case(integer(7), integer(0) => None; "division by zero")]
+fn floor_div(n: Number, d: Number) -> Option<(String, String)> {
+    n.floor_div(&d).map(|(q, r)| (q.to_string(), r.to_string()))
+}
+
+#[test_case(integer(-7), integer(2) => Some(("-3".into(), "-1".into())); "negative dividend")]
+#[test_case(integer(7), real(-2.0) => Some(("-3.0".into(), "1.0".into())); "inexact divisor")]
+#[test_case(real(5.0), real(0.0) => None; "inexact division by zero")]
+fn truncate_div(n: Number, d: Number) -> Option<(String, String)> {
+    n.truncate_div(&d).map(|(q, r)| (q.to_string(), r.to_string()))
+}
+
+#[test_case(integer(12), integer(-18) => "6"; "exact integers")]
+#[test_case(integer(12), real(18.0) => "6.0"; "inexact integer")]
+#[test_case(integer(0), integer(5) => "5"; "zero")]
+fn gcd(a: Number, b: Number) -> String {
+    a.gcd(&b).unwrap().to_string()
+}
+
+#[test_case(integer(4), integer(-6) => "12"; "exact integers")]
+#[test_case(integer(0), integer(5) => "0"; "zero")]
+fn lcm(a: Number, b: Number) -> String {
+    a.lcm(&b).unwrap().to_string()
+}
+
+#[test_case(integer(1), real(2.0) => ("1.0".into(), "2.0".into()); "inexact contagion")]
+#[test_case(rational(1, 2), rational(1, 3) => ("1/3".into(), "1/2".into()); "exact rationals")]
+#[test_case(integer(1), real(f64::NAN) => ("+nan.0".into(), "+nan.0".into()); "nan")]
+fn min_max(a: Number, b: Number) -> (String, String) {
+    (a.min(&b).to_string(), a.max(&b).to_string())
+}
+
+#[test_case(integer(2), integer(10) => Some("1024".into()); "exact power")]
+#[test_case(rational(2, 3), integer(-2) => Some("9/4".into()); "exact negative power")]
+#[test_case(integer(2), integer(100) => Some("1267650600228229401496703205376".into()); "big power")]
+#[test_case(real(2.0), integer(3) => Some("8.0".into()); "inexact base")]
+#[test_case(integer(4), rational(1, 2) => Some("2.0".into()); "rational power")]
+#[test_case(real(1.5), integer(0) => Some("1".into()); "exact zero power")]
+#[test_case(integer(-1), rational(1, 2) => Some("6.123233995736766e-17+1.0i".into()); "complex root")]
+#[test_case(integer(0), integer(-1) => None; "exact zero to negative power")]
+fn expt(base: Number, power: Number) -> Option<String> {
+    base.expt(&power).map(|n| n.to_string())
+}
+
+#[test_case(integer(17) => Some((integer(4), integer(1))); "non-square")]
+#[test_case(integer(16) => Some((integer(4), integer(0))); "perfect square")]
+#[test_case(integer(-1) => None; "negative")]
+#[test_case(real(4.0) => None; "inexact")]
+fn exact_integer_sqrt(value: Number) -> Option<(Number, Number)> {
+    value.exact_integer_sqrt()
+}
+
+#[test_case(rational(7, 2) => ("3".into(), "4".into(), "4".into(), "3".into()); "positive rational")]
+#[test_case(rational(-7, 2) => ("-4".into(), "-3".into(), "-4".into(), "-3".into()); "negative rational")]
+#[test_case(rational(5, 2) => ("2".into(), "3".into(), "2".into(), "2".into()); "tie rounds to even")]
+#[test_case(real(-2.5) => ("-3.0".into(), "-2.0".into(), "-2.0".into(), "-2.0".into()); "inexact tie")]
+#[test_case(real(0.6) => ("0.0".into(), "1.0".into(), "1.0".into(), "0.0".into()); "inexact fraction")]
+#[test_case(integer(3) => ("3".into(), "3".into(), "3".into(), "3".into()); "integer value")]
+fn rounding(value: Number) -> (String, String, String, String) {
+    (
+        value.floor().to_string(),
+        value.ceiling().to_string(),
+        value.round().to_string(),
+        value.truncate().to_string(),
+    )
+}
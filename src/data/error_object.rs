@@ -0,0 +1,76 @@
+use std::cell::RefCell;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::rc::Rc;
+
+use crate::data::{Expr, Expression};
+use crate::eval::{EvalError, EvalErrorKind};
+
+/// An error object, as raised by `error` or made from an error inside the evaluator.
+pub struct ErrorObject {
+    pub kind: ErrorObjectKind,
+    pub message: String,
+    pub irritants: Vec<Expr>,
+    /// The evaluator error the object was made from, which is reported in its place if the
+    /// object is raised again and not caught.
+    pub error: Option<EvalError>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorObjectKind {
+    Error,
+    Read,
+    File,
+}
+
+impl ErrorObject {
+    pub fn new(message: String, irritants: Vec<Expr>) -> Self {
+        ErrorObject { kind: ErrorObjectKind::Error, message, irritants, error: None }
+    }
+
+    /// Marks the object as a read or file error.
+    pub fn with_kind(mut self, kind: ErrorObjectKind) -> Self {
+        self.kind = kind;
+        self
+    }
+}
+
+/// The object's message is the error's description, and its irritants are the values the
+/// error is about.
+impl From<EvalError> for ErrorObject {
+    fn from(err: EvalError) -> ErrorObject {
+        use EvalErrorKind::*;
+
+        let irritants = match &err.kind {
+            UnboundVariable(name) => vec![Rc::new(Expression::Symbol(name.clone()))],
+            WrongType { actual, .. } => vec![actual.clone()],
+            Arity { procedure, .. } => vec![procedure.clone()],
+            NotProcedure(value) => vec![value.clone()],
+            BadSyntax { form, .. } => vec![form.clone()],
+            _ => vec![],
+        };
+
+        ErrorObject { kind: ErrorObjectKind::Error, message: err.kind.to_string(), irritants, error: Some(err) }
+    }
+}
+
+impl PartialEq for ErrorObject {
+    fn eq(&self, other: &ErrorObject) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Debug for ErrorObject {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Display::fmt(self, f)
+    }
+}
+
+impl Display for ErrorObject {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "#<error {}", Expression::String(RefCell::new(self.message.clone())))?;
+        for irritant in &self.irritants {
+            write!(f, " {}", irritant)?;
+        }
+        write!(f, ">")
+    }
+}
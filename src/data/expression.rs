@@ -1,46 +1,223 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::rc::Rc;
 
-use crate::data::Number;
-use crate::read::is_simple_identifier;
+use crate::data::{Alias, Closure, Control, ErrorObject, Macro, Number, Primitive, Values};
+use crate::eval::Continuation;
+use crate::read::{is_simple_identifier, CHAR_NAMES};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Expression {
     Nil,
+    Boolean(bool),
+    Char(char),
     Number(Number),
+    String(RefCell<String>),
     Symbol(String),
     Pair(Expr, Expr),
+    Vector(RefCell<Vec<Expr>>),
+    Bytevector(RefCell<Vec<u8>>),
+    Closure(Rc<Closure>),
+    Primitive(Rc<Primitive>),
+    Control(Control),
+    Continuation(Rc<Continuation>),
+    ErrorObject(Rc<ErrorObject>),
+    /// The values passed to a continuation that takes other than exactly one value.
+    Values(Values),
+    Alias(Rc<Alias>),
+    Macro(Rc<Macro>),
+    Unspecified,
 }
 
 pub type Expr = Rc<Expression>;
 
-impl Display for Expression {
-    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+impl Expression {
+    pub fn list(items: Vec<Expr>) -> Expr {
+        items.into_iter().rev().fold(Rc::new(Expression::Nil), |tail, head| {
+            Rc::new(Expression::Pair(head, tail))
+        })
+    }
+
+    /// Everything except `#f` counts as true in a conditional.
+    pub fn is_true(&self) -> bool {
+        !matches!(self, Expression::Boolean(false))
+    }
+
+    pub fn is_procedure(&self) -> bool {
         use Expression::*;
 
-        fn fmt_symbol(name: &str, f: &mut Formatter) -> FmtResult {
-            if is_simple_identifier(name) {
-                return name.fmt(f);
+        matches!(self, Closure(_) | Primitive(_) | Control(_) | Continuation(_))
+    }
+
+    pub fn is_identifier(&self) -> bool {
+        matches!(self, Expression::Symbol(_) | Expression::Alias(_))
+    }
+
+    /// Returns the name of a symbol, or of the symbol an alias was renamed from.
+    pub fn identifier_name(&self) -> Option<&str> {
+        match self {
+            Expression::Symbol(name) => Some(name),
+            Expression::Alias(alias) => alias.name.identifier_name(),
+            _ => None,
+        }
+    }
+
+    /// Tests `eqv?` equivalence: atoms by value, everything else by identity.
+    pub fn eqv(&self, other: &Expression) -> bool {
+        use Expression::*;
+
+        match (self, other) {
+            (Nil, Nil) | (Unspecified, Unspecified) => true,
+            (Boolean(a), Boolean(b)) => a == b,
+            (Char(a), Char(b)) => a == b,
+            (Number(a), Number(b)) => a.eqv(b),
+            (Symbol(a), Symbol(b)) => a == b,
+            (Closure(a), Closure(b)) => Rc::ptr_eq(a, b),
+            (Primitive(a), Primitive(b)) => Rc::ptr_eq(a, b),
+            (Control(a), Control(b)) => a == b,
+            (Continuation(a), Continuation(b)) => Rc::ptr_eq(a, b),
+            (ErrorObject(a), ErrorObject(b)) => Rc::ptr_eq(a, b),
+            (Alias(a), Alias(b)) => Rc::ptr_eq(a, b),
+            (Macro(a), Macro(b)) => Rc::ptr_eq(a, b),
+            _ => std::ptr::eq(self, other),
+        }
+    }
+
+    /// Tests `equal?` equivalence: compares pairs, strings and vectors recursively by contents.
+    pub fn equal(&self, other: &Expression) -> bool {
+        self.equal_visiting(other, &mut HashSet::new())
+    }
+
+    /// Compares by contents, remembering the pairs and vectors already compared. Meeting the
+    /// same two again means they are equal unless some other part differs, which keeps circular
+    /// structures from recursing forever.
+    fn equal_visiting(&self, other: &Expression, visited: &mut HashSet<(*const Expression, *const Expression)>) -> bool {
+        use Expression::*;
+
+        let (mut a, mut b) = (self, other);
+
+        loop {
+            match (a, b) {
+                (Pair(..), Pair(..)) | (Vector(_), Vector(_)) if !visited.insert((a, b)) => return true,
+                (Pair(h1, t1), Pair(h2, t2)) => {
+                    if !h1.equal_visiting(h2, visited) {
+                        return false;
+                    }
+
+                    a = t1;
+                    b = t2;
+                },
+                (String(x), String(y)) => return x == y,
+                (Vector(x), Vector(y)) => {
+                    let (x, y) = (x.borrow(), y.borrow());
+                    return x.len() == y.len() && x.iter().zip(y.iter()).all(|(x, y)| x.equal_visiting(y, visited));
+                },
+                (Bytevector(x), Bytevector(y)) => return x == y,
+                _ => return a.eqv(b),
+            }
+        }
+    }
+
+    /// Tests whether the expression is a proper list, ending in `()`.
+    pub fn is_list(&self) -> bool {
+        let mut tail = self;
+        while let Expression::Pair(_, t) = tail {
+            tail = t;
+        }
+
+        matches!(tail, Expression::Nil)
+    }
+
+    pub fn list_to_vec(&self) -> Option<Vec<Expr>> {
+        let mut items = Vec::new();
+        let mut tail = self;
+
+        loop {
+            match tail {
+                Expression::Nil => return Some(items),
+                Expression::Pair(h, t) => {
+                    items.push(h.clone());
+                    tail = t;
+                },
+                _ => return None,
             }
+        }
+    }
+}
 
-            write!(f, "|")?;
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use Expression::*;
+
+        fn fmt_escaped(text: &str, quote: char, f: &mut Formatter) -> FmtResult {
+            quote.fmt(f)?;
 
-            for ch in name.chars() {
+            for ch in text.chars() {
                 match ch {
                     '\u{0007}' => write!(f, "\\a")?,
                     '\u{0008}' => write!(f, "\\b")?,
                     '\t' => write!(f, "\\t")?,
                     '\n' => write!(f, "\\n")?,
                     '\r' => write!(f, "\\r")?,
+                    '\\' => write!(f, "\\\\")?,
+                    _ if ch == quote => write!(f, "\\{}", ch)?,
                     _ if ch.is_alphanumeric() || ch.is_ascii_graphic() || ch == ' ' => ch.fmt(f)?,
                     _ => write!(f, "\\x{:x};", ch as u32)?,
                 }
             }
 
-            write!(f, "|")
+            quote.fmt(f)
+        }
+
+        fn fmt_symbol(name: &str, f: &mut Formatter) -> FmtResult {
+            if is_simple_identifier(name) {
+                return name.fmt(f);
+            }
+
+            fmt_escaped(name, '|', f)
+        }
+
+        fn fmt_char(ch: char, f: &mut Formatter) -> FmtResult {
+            if let Some((name, _)) = CHAR_NAMES.iter().find(|(_, c)| *c == ch) {
+                write!(f, "#\\{}", name)
+            } else if ch.is_alphanumeric() || ch.is_ascii_graphic() {
+                write!(f, "#\\{}", ch)
+            } else {
+                write!(f, "#\\x{:x}", ch as u32)
+            }
+        }
+
+        fn fmt_items<T: Display>(prefix: &str, items: &[T], f: &mut Formatter) -> FmtResult {
+            write!(f, "{}(", prefix)?;
+
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " ")?;
+                }
+                item.fmt(f)?;
+            }
+
+            write!(f, ")")
         }
 
         fn fmt_list(head: &Expression, mut tail: &Expression, f: &mut Formatter) -> FmtResult {
+            if let (Symbol(name), Pair(datum, rest)) = (head, tail) {
+                if let Nil = **rest {
+                    let prefix = match name.as_str() {
+                        "quote" => Some("'"),
+                        "quasiquote" => Some("`"),
+                        "unquote" => Some(","),
+                        "unquote-splicing" => Some(",@"),
+                        _ => None,
+                    };
+
+                    if let Some(prefix) = prefix {
+                        return write!(f, "{}{}", prefix, datum);
+                    }
+                }
+            }
+
             write!(f, "({}", head)?;
 
             loop {
@@ -62,9 +239,30 @@ impl Display for Expression {
 
         match self {
             Nil => write!(f, "()"),
+            Boolean(true) => write!(f, "#t"),
+            Boolean(false) => write!(f, "#f"),
+            Char(ch) => fmt_char(*ch, f),
             Number(n) => n.fmt(f),
+            String(s) => fmt_escaped(&s.borrow(), '"', f),
             Symbol(n) => fmt_symbol(n, f),
             Pair(h, t) => fmt_list(h, t, f),
+            Vector(items) => fmt_items("#", &items.borrow(), f),
+            Bytevector(bytes) => fmt_items("#u8", &bytes.borrow(), f),
+            Closure(c) => c.fmt(f),
+            Primitive(p) => p.fmt(f),
+            Control(c) => c.fmt(f),
+            Continuation(k) => k.fmt(f),
+            ErrorObject(e) => e.fmt(f),
+            Values(values) => {
+                write!(f, "#<values")?;
+                for value in values {
+                    write!(f, " {}", value)?;
+                }
+                write!(f, ">")
+            },
+            Alias(a) => a.fmt(f),
+            Macro(m) => m.fmt(f),
+            Unspecified => write!(f, "#<unspecified>"),
         }
     }
 }
@@ -7,28 +7,83 @@ use crate::data::test_helpers::*;
 
 #[test_case(nil(), nil() => true; "nil equals self")]
 #[test_case(nil(), symbol("nil") => false; "nil not equal to symbol `nil`")]
+#[test_case(boolean(true), boolean(true) => true; "same booleans equal")]
+#[test_case(boolean(true), boolean(false) => false; "different booleans not equal")]
+#[test_case(boolean(false), nil() => false; "false not equal to nil")]
+#[test_case(character('a'), character('a') => true; "same characters equal")]
+#[test_case(character('a'), character('A') => false; "characters are case sensitive")]
+#[test_case(character('a'), symbol("a") => false; "character not equal to symbol")]
 #[test_case(number(integer(5)), number(real(5.0)) => true; "equal numbers equal")]
 #[test_case(number(integer(-7)), number(real(-7.1)) => false; "inequal numbers not equal")]
 #[test_case(number(integer(43)), symbol("43") => false; "number not equal to symbol of number")]
+#[test_case(string("abc"), string("abc") => true; "strings with same contents equal")]
+#[test_case(string("abc"), string("abd") => false; "strings with different contents not equal")]
+#[test_case(string("abc"), symbol("abc") => false; "string not equal to symbol")]
 #[test_case(symbol("test"), symbol("test") => true; "symbols with same name equal")]
 #[test_case(symbol("a"), symbol("ab") => false; "symbols with different names not equal")]
 #[test_case(pair(symbol("x"), symbol("y")), pair(symbol("x"), symbol("y")) => true; "identical pairs equal")]
 #[test_case(list!(symbol("x"), symbol("y")), list!(symbol("x"), symbol("y")) => true; "identical lists equal")]
 #[test_case(pair(symbol("x"), symbol("y")), list!(symbol("x"), symbol("y")) => false; "list and pair not equal")]
+#[test_case(vector(vec![symbol("x")]), vector(vec![symbol("x")]) => true; "identical vectors equal")]
+#[test_case(vector(vec![symbol("x")]), list!(symbol("x")) => false; "vector and list not equal")]
+#[test_case(bytevector(vec![1, 2]), bytevector(vec![1, 2]) => true; "identical bytevectors equal")]
+#[test_case(bytevector(vec![1, 2]), vector(vec![number(integer(1)), number(integer(2))]) => false; "bytevector and vector not equal")]
 fn equality(a: Expr, b: Expr) -> bool {
     a == b
 }
 
+#[test_case(number(integer(5)), number(integer(5)) => (true, true); "equal exact numbers")]
+#[test_case(number(integer(5)), number(real(5.0)) => (false, false); "exact and inexact numbers")]
+#[test_case(symbol("a"), symbol("a") => (true, true); "symbols")]
+#[test_case(nil(), nil() => (true, true); "empty lists")]
+#[test_case(string("abc"), string("abc") => (false, true); "distinct strings")]
+#[test_case(list!(number(integer(1))), list!(number(integer(1))) => (false, true); "distinct lists")]
+#[test_case(vector(vec![string("a")]), vector(vec![string("a")]) => (false, true); "distinct vectors")]
+#[test_case(list!(number(integer(1))), list!(number(real(1.0))) => (false, false); "lists with different exactness")]
+fn equivalence(a: Expr, b: Expr) -> (bool, bool) {
+    (a.eqv(&b), a.equal(&b))
+}
+
+#[test]
+fn procedure_equality_is_identity() {
+    let a = primitive("f");
+    let b = primitive("f");
+
+    assert_eq!(a, a.clone());
+    assert_eq!(list!(a.clone()), list!(a.clone()));
+    assert_ne!(a, b);
+}
+
 #[test_case(nil() => "()"; "nil value")]
+#[test_case(boolean(true) => "#t"; "true value")]
+#[test_case(boolean(false) => "#f"; "false value")]
+#[test_case(character('a') => "#\\a"; "simple character")]
+#[test_case(character('(') => "#\\("; "delimiter character")]
+#[test_case(character(' ') => "#\\space"; "named character")]
+#[test_case(character('\u{0}') => "#\\null"; "null character")]
+#[test_case(character('\u{85}') => "#\\x85"; "unprintable character")]
 #[test_case(number(integer(-5)) => "-5"; "integral number")]
 #[test_case(number(real(12.34)) => "12.34"; "real number")]
+#[test_case(string("hello") => "\"hello\""; "simple string")]
+#[test_case(string("say \"hi\"\\") => "\"say \\\"hi\\\"\\\\\""; "string with quotes")]
+#[test_case(string("tab\tnew\n|\u{0}") => "\"tab\\tnew\\n|\\x0;\""; "escaped string")]
 #[test_case(symbol("+") => "+"; "simple symbol")]
 #[test_case(symbol("test") => "test"; "text symbol")]
 #[test_case(symbol("quoted name") => "|quoted name|"; "quoted symbol")]
 #[test_case(symbol("escaped\nname\u{0}") => "|escaped\\nname\\x0;|"; "escaped symbol")]
+#[test_case(symbol("a|b") => "|a\\|b|"; "symbol with vertical line")]
 #[test_case(pair(symbol("a"), symbol("b")) => "(a . b)"; "simple pair")]
 #[test_case(pair(symbol("a"), pair(symbol("b"), symbol("c"))) => "(a b . c)"; "dotted list")]
 #[test_case(list!(symbol("a"), symbol("b"), symbol("c")) => "(a b c)"; "regular list")]
+#[test_case(vector(vec![]) => "#()"; "empty vector")]
+#[test_case(vector(vec![symbol("a"), list!(symbol("b")), string("c")]) => "#(a (b) \"c\")"; "vector value")]
+#[test_case(bytevector(vec![]) => "#u8()"; "empty bytevector")]
+#[test_case(bytevector(vec![0, 127, 255]) => "#u8(0 127 255)"; "bytevector value")]
+#[test_case(list!(symbol("quote"), symbol("a")) => "'a"; "quote form")]
+#[test_case(list!(symbol("quasiquote"), list!(symbol("unquote"), symbol("a"))) => "`,a"; "quasiquote form")]
+#[test_case(list!(symbol("unquote-splicing"), list!(symbol("a"))) => ",@(a)"; "unquote splicing form")]
+#[test_case(list!(symbol("quote"), symbol("a"), symbol("b")) => "(quote a b)"; "malformed quote form")]
+#[test_case(primitive("car") => "#<procedure car>"; "primitive procedure")]
 fn formatting(value: Expr) -> String {
     value.to_string()
 }
@@ -1,10 +1,22 @@
+mod arithmetic;
+mod error_object;
 mod expression;
 mod number;
+mod procedure;
+mod source;
+mod syntax;
+mod values;
 
+pub use self::error_object::*;
 pub use self::expression::*;
 pub use self::number::*;
+pub use self::procedure::*;
+pub use self::source::*;
+pub use self::syntax::*;
+pub use self::values::*;
 
 #[macro_use]
 pub mod test_helpers;
+mod arithmetic_tests;
 mod expression_tests;
 mod number_tests;
@@ -1,25 +1,107 @@
+use std::cmp::Ordering;
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
-use num::{BigInt, ToPrimitive};
+use num::complex::{Complex, Complex64};
+use num::{BigInt, BigRational, One, Signed};
 
 #[derive(Clone, Debug)]
 pub enum Number {
     Integer(BigInt),
+    Rational(BigRational),
     Real(f64),
+    Complex(Complex64),
+    /// An exact complex number, whose imaginary part is never zero.
+    ExactComplex(Complex<BigRational>),
+}
+
+impl Number {
+    pub fn rational(value: BigRational) -> Number {
+        if value.denom().is_one() {
+            Number::Integer(value.to_integer())
+        } else {
+            Number::Rational(value)
+        }
+    }
+
+    /// Formats the number in the given radix. Only exact numbers can be written in a radix
+    /// other than 10.
+    pub fn to_string_radix(&self, radix: u32) -> Option<String> {
+        match self {
+            _ if radix == 10 => Some(self.to_string()),
+            Number::Integer(i) => Some(i.to_str_radix(radix)),
+            Number::Rational(r) => Some(format!("{}/{}", r.numer().to_str_radix(radix), r.denom().to_str_radix(radix))),
+            Number::ExactComplex(c) => {
+                let real = Number::rational(c.re.clone()).to_string_radix(radix)?;
+                let imag = Number::rational(c.im.clone()).to_string_radix(radix)?;
+                Some(format!("{}{}{}i", real, if c.im.is_negative() { "" } else { "+" }, imag))
+            },
+            _ => None,
+        }
+    }
+}
+
+impl Number {
+    /// Tests operational equivalence: numbers must agree in exactness as well as value.
+    pub fn eqv(&self, other: &Number) -> bool {
+        use Number::*;
+
+        fn same_float(a: f64, b: f64) -> bool {
+            a.to_bits() == b.to_bits() || (a.is_nan() && b.is_nan())
+        }
+
+        match (self, other) {
+            (Real(a), Real(b)) => same_float(*a, *b),
+            (Complex(a), Complex(b)) => same_float(a.re, b.re) && same_float(a.im, b.im),
+            (ExactComplex(a), ExactComplex(b)) => a == b,
+            (Real(_), _) | (_, Real(_)) | (Complex(_), _) | (_, Complex(_)) => false,
+            _ => self == other,
+        }
+    }
 }
 
 impl PartialEq for Number {
     fn eq(&self, other: &Number) -> bool {
         use Number::*;
 
-        fn i2r(integer: &BigInt) -> f64 {
-            integer.to_f64().unwrap_or(f64::NAN)
+        match (self, other) {
+            (Complex(a), Complex(b)) => a == b,
+            (ExactComplex(a), ExactComplex(b)) => a == b,
+            (Complex(a), b @ ExactComplex(_)) | (b @ ExactComplex(_), Complex(a)) => *a == b.to_complex(),
+            (ExactComplex(_), _) | (_, ExactComplex(_)) => false,
+            (Complex(a), b) | (b, Complex(a)) => a.im == 0.0 && Real(a.re) == *b,
+            _ => self.partial_cmp(other) == Some(Ordering::Equal),
+        }
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Number) -> Option<Ordering> {
+        use Number::*;
+
+        fn exact(number: &Number) -> BigRational {
+            match number {
+                Integer(i) => BigRational::from_integer(i.clone()),
+                Rational(r) => r.clone(),
+                _ => unreachable!(),
+            }
+        }
+
+        fn compare_real(real: f64, exact_value: &Number) -> Option<Ordering> {
+            if real.is_nan() {
+                None
+            } else if real.is_infinite() {
+                Some(if real > 0.0 { Ordering::Greater } else { Ordering::Less })
+            } else {
+                BigRational::from_float(real).map(|r| r.cmp(&exact(exact_value)))
+            }
         }
 
         match (self, other) {
-            (Integer(a), Integer(b)) => a == b,
-            (Integer(a), Real(b)) => &i2r(a) == b,
-            (Real(a), Integer(b)) => a == &i2r(b),
-            (Real(a), Real(b)) => a == b,
+            (Complex(_) | ExactComplex(_), _) | (_, Complex(_) | ExactComplex(_)) => if self == other { Some(Ordering::Equal) } else { None },
+            (Integer(a), Integer(b)) => Some(a.cmp(b)),
+            (Real(a), Real(b)) => a.partial_cmp(b),
+            (Real(a), b) => compare_real(*a, b),
+            (a, Real(b)) => compare_real(*b, a).map(Ordering::reverse),
+            (a, b) => Some(exact(a).cmp(&exact(b))),
         }
     }
 }
@@ -28,9 +110,36 @@ impl Display for Number {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         use Number::*;
 
+        fn fmt_real(r: f64, f: &mut Formatter) -> FmtResult {
+            if r.is_nan() {
+                write!(f, "+nan.0")
+            } else if r.is_infinite() {
+                write!(f, "{}inf.0", if r < 0.0 { "-" } else { "+" })
+            } else {
+                Debug::fmt(&r, f)
+            }
+        }
+
         match self {
             Integer(i) => Display::fmt(i, f),
-            Real(r) => Debug::fmt(r, f),
+            Rational(r) => Display::fmt(r, f),
+            Real(r) => fmt_real(*r, f),
+            Complex(c) => {
+                fmt_real(c.re, f)?;
+                if c.im.is_finite() && !c.im.is_sign_negative() {
+                    write!(f, "+")?;
+                }
+                fmt_real(c.im, f)?;
+                write!(f, "i")
+            },
+            ExactComplex(c) => {
+                Display::fmt(&Number::rational(c.re.clone()), f)?;
+                if !c.im.is_negative() {
+                    write!(f, "+")?;
+                }
+                Display::fmt(&Number::rational(c.im.clone()), f)?;
+                write!(f, "i")
+            },
         }
     }
 }
@@ -1,5 +1,7 @@
 #![cfg(test)]
 
+use std::cmp::Ordering;
+
 use test_case::test_case;
 
 use crate::data::Number;
@@ -9,19 +11,80 @@ use crate::data::test_helpers::*;
 #[test_case(integer(-5), integer(5) => false; "inequal integers")]
 #[test_case(real(8.32), real(8.32) => true; "equal decimals")]
 #[test_case(real(8.32), real(8.33) => false; "inequal decimals")]
+#[test_case(rational(1, 3), rational(2, 6) => true; "equal rationals")]
+#[test_case(rational(1, 3), rational(-1, 3) => false; "inequal rationals")]
+#[test_case(rational(4, 2), integer(2) => true; "rational normalised to integer")]
+#[test_case(rational(1, 2), real(0.5) => true; "equal rational and real")]
+#[test_case(rational(1, 3), real(0.3) => false; "inequal rational and real")]
+#[test_case(complex(1.0, 2.0), complex(1.0, 2.0) => true; "equal complex numbers")]
+#[test_case(complex(1.0, 2.0), complex(1.0, -2.0) => false; "inequal complex numbers")]
+#[test_case(complex(1.0, 2.0), real(1.0) => false; "complex and real")]
 #[test_case(integer(-45), real(-45.0) => true; "equal integer and real")]
 #[test_case(integer(3), real(3.001) => false; "inequal integer and real")]
+#[test_case(integer_str("9007199254740993", 10), real(9007199254740992.0) => false; "integer just beyond f64 precision")]
+#[test_case(integer_str("9007199254740992", 10), real(9007199254740992.0) => true; "integer at f64 precision")]
+#[test_case(integer_str(format!("1{}", "0".repeat(400)), 10), real(f64::INFINITY) => false; "integer beyond f64 range")]
+#[test_case(rational(1, 10), real(0.1) => false; "rational not exactly representable")]
+#[test_case(real(f64::NAN), real(f64::NAN) => false; "nan not equal to itself")]
 fn equality(a: Number, b: Number) -> bool {
     a == b
 }
 
+#[test_case(integer(1), integer(2) => Some(Ordering::Less); "integers")]
+#[test_case(rational(1, 3), real(0.3333333333333333) => Some(Ordering::Greater); "rational and nearest real")]
+#[test_case(integer_str("9007199254740993", 10), real(9007199254740992.0) => Some(Ordering::Greater); "large integer and real")]
+#[test_case(real(f64::INFINITY), integer_str("1000000000000000000000000000000000000000", 10) => Some(Ordering::Greater); "infinity and integer")]
+#[test_case(integer(0), real(f64::NEG_INFINITY) => Some(Ordering::Greater); "integer and negative infinity")]
+#[test_case(real(f64::NAN), integer(0) => None; "nan and integer")]
+#[test_case(complex(1.0, 1.0), integer(0) => None; "complex and integer")]
+fn ordering(a: Number, b: Number) -> Option<Ordering> {
+    a.partial_cmp(&b)
+}
+
+#[test_case(integer(2), integer(2) => true; "equal integers")]
+#[test_case(integer(2), real(2.0) => false; "exact and inexact")]
+#[test_case(rational(1, 2), rational(2, 4) => true; "equal rationals")]
+#[test_case(real(0.0), real(-0.0) => false; "signed zeros")]
+#[test_case(real(f64::NAN), real(f64::NAN) => true; "nans")]
+#[test_case(complex(1.0, 2.0), complex(1.0, 2.0) => true; "equal complex numbers")]
+fn eqv(a: Number, b: Number) -> bool {
+    a.eqv(&b)
+}
+
 #[test_case(integer(984) => "984"; "positive integer")]
 #[test_case(integer(-3) => "-3"; "negative integer")]
 #[test_case(integer_str("98765432123456789", 10) => "98765432123456789"; "large integer")]
+#[test_case(rational(1, 3) => "1/3"; "positive rational")]
+#[test_case(rational(22, -7) => "-22/7"; "negative rational")]
+#[test_case(rational(6, 4) => "3/2"; "reduced rational")]
+#[test_case(rational(-9, 3) => "-3"; "integral rational")]
 #[test_case(real(1.765) => "1.765"; "positive real")]
 #[test_case(real(-87.654) => "-87.654"; "negative real")]
 #[test_case(real(0.234) => "0.234"; "fraction real")]
 #[test_case(real(97.0) => "97.0"; "integral real")]
+#[test_case(complex(1.0, 2.0) => "1.0+2.0i"; "complex with positive imaginary part")]
+#[test_case(complex(-1.5, -0.5) => "-1.5-0.5i"; "complex with negative imaginary part")]
+#[test_case(complex(0.0, 1.0) => "0.0+1.0i"; "imaginary complex")]
+#[test_case(exact_complex(rational(1, 2), integer(-3)) => "1/2-3i"; "exact complex number")]
+#[test_case(exact_complex(integer(0), integer(1)) => "0+1i"; "exact imaginary number")]
+#[test_case(real(f64::INFINITY) => "+inf.0"; "positive infinity")]
+#[test_case(real(f64::NEG_INFINITY) => "-inf.0"; "negative infinity")]
+#[test_case(real(f64::NAN) => "+nan.0"; "not a number")]
+#[test_case(real(-0.0) => "-0.0"; "negative zero")]
+#[test_case(real(1e21) => "1e21"; "large real")]
+#[test_case(real(1.5e-7) => "1.5e-7"; "small real")]
+#[test_case(complex(1.0, f64::NEG_INFINITY) => "1.0-inf.0i"; "complex with infinite imaginary part")]
+#[test_case(complex(f64::NAN, f64::NAN) => "+nan.0+nan.0i"; "complex with nan parts")]
 fn formatting(value: Number) -> String {
     value.to_string()
 }
+
+#[test_case(integer(255), 16 => Some("ff".to_string()); "hex integer")]
+#[test_case(integer(-5), 2 => Some("-101".to_string()); "negative binary integer")]
+#[test_case(rational(-1, 8), 8 => Some("-1/10".to_string()); "octal rational")]
+#[test_case(real(1.5), 10 => Some("1.5".to_string()); "decimal real")]
+#[test_case(real(2.0), 2 => None; "binary real")]
+#[test_case(complex(1.0, 1.0), 16 => None; "hex complex")]
+fn to_string_radix(value: Number, radix: u32) -> Option<String> {
+    value.to_string_radix(radix)
+}
@@ -0,0 +1,118 @@
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::rc::Rc;
+
+use crate::data::Expr;
+use crate::eval::{Env, EvalResult};
+
+pub struct Closure {
+    pub name: Option<String>,
+    pub params: Vec<String>,
+    pub rest: Option<String>,
+    pub body: Rc<[Expr]>,
+    pub env: Env,
+}
+
+pub type PrimitiveFn = Box<dyn Fn(&[Expr]) -> EvalResult<Expr>>;
+
+pub struct Primitive {
+    pub name: &'static str,
+    pub min_args: usize,
+    pub max_args: Option<usize>,
+    pub func: PrimitiveFn,
+}
+
+/// A procedure the evaluator implements itself, because it needs access to the control stack.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Control {
+    CallWithCurrentContinuation,
+    DynamicWind,
+    WithExceptionHandler,
+    RaiseContinuable,
+    CallWithValues,
+    Load,
+}
+
+impl Closure {
+    pub fn accepts(&self, count: usize) -> bool {
+        match self.rest {
+            Some(_) => count >= self.params.len(),
+            None => count == self.params.len(),
+        }
+    }
+}
+
+impl Primitive {
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min_args && self.max_args.is_none_or(|max| count <= max)
+    }
+}
+
+impl Control {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Control::CallWithCurrentContinuation => "call-with-current-continuation",
+            Control::DynamicWind => "dynamic-wind",
+            Control::WithExceptionHandler => "with-exception-handler",
+            Control::RaiseContinuable => "raise-continuable",
+            Control::CallWithValues => "call-with-values",
+            Control::Load => "load",
+        }
+    }
+
+    /// The minimum and maximum number of arguments the procedure accepts.
+    pub fn arity(&self) -> (usize, Option<usize>) {
+        match self {
+            Control::CallWithCurrentContinuation => (1, Some(1)),
+            Control::DynamicWind => (3, Some(3)),
+            Control::WithExceptionHandler => (2, Some(2)),
+            Control::RaiseContinuable => (1, Some(1)),
+            Control::CallWithValues => (2, Some(2)),
+            Control::Load => (1, Some(1)),
+        }
+    }
+}
+
+impl PartialEq for Closure {
+    fn eq(&self, other: &Closure) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Debug for Closure {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Display::fmt(self, f)
+    }
+}
+
+impl Display for Closure {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match &self.name {
+            Some(name) => write!(f, "#<procedure {}>", name),
+            None => write!(f, "#<procedure>"),
+        }
+    }
+}
+
+impl PartialEq for Primitive {
+    fn eq(&self, other: &Primitive) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Debug for Primitive {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Display::fmt(self, f)
+    }
+}
+
+impl Display for Primitive {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "#<procedure {}>", self.name)
+    }
+}
+
+impl Display for Control {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "#<procedure {}>", self.name())
+    }
+}
@@ -0,0 +1,83 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::rc::{Rc, Weak};
+
+use crate::data::{Expr, Expression};
+
+/// A named piece of source text, such as a file or a line entered at the REPL.
+#[derive(Debug)]
+pub struct Source {
+    pub name: String,
+    pub text: String,
+}
+
+/// The location of a datum within its source text, as a byte range.
+#[derive(Clone)]
+pub struct Span {
+    pub source: Rc<Source>,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Source {
+    pub fn new<N: ToString, T: ToString>(name: N, text: T) -> Rc<Source> {
+        Rc::new(Source { name: name.to_string(), text: text.to_string() })
+    }
+
+    /// Converts a byte offset into a one-based line and column, counting columns in characters.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let before = &self.text[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+        (line, before[line_start..].chars().count() + 1)
+    }
+}
+
+impl Span {
+    pub fn line(&self) -> usize {
+        self.source.line_col(self.start).0
+    }
+
+    pub fn column(&self) -> usize {
+        self.source.line_col(self.start).1
+    }
+
+    pub fn text(&self) -> &str {
+        &self.source.text[self.start..self.end]
+    }
+}
+
+impl Debug for Span {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{} ({}..{})", self, self.start, self.end)
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}:{}:{}", self.source.name, self.line(), self.column())
+    }
+}
+
+thread_local! {
+    // Keyed by address; the weak reference keeps the allocation, and so the address, reserved
+    // until the entry is pruned.
+    static SPANS: RefCell<HashMap<*const Expression, (Weak<Expression>, Span)>> = RefCell::new(HashMap::new());
+}
+
+/// Records where an expression was read from.
+pub fn set_span(expr: &Expr, span: Span) {
+    SPANS.with(|spans| spans.borrow_mut().insert(Rc::as_ptr(expr), (Rc::downgrade(expr), span)));
+}
+
+/// Looks up where an expression was read from, if it came from the reader.
+pub fn span_of(expr: &Expr) -> Option<Span> {
+    SPANS.with(|spans| spans.borrow().get(&Rc::as_ptr(expr)).map(|(_, span)| span.clone()))
+}
+
+/// Forgets the locations of expressions that no longer exist.
+pub fn prune_spans() {
+    SPANS.with(|spans| spans.borrow_mut().retain(|_, (expr, _)| expr.strong_count() > 0));
+}
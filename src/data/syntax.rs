@@ -0,0 +1,63 @@
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::rc::Rc;
+
+use crate::data::Expr;
+use crate::eval::{Scope, SyntaxRules};
+
+/// An identifier introduced by a macro expansion. It refers to whatever `name` means in the
+/// scope where the macro was defined, unless a binding form in the expansion binds the alias
+/// itself.
+pub struct Alias {
+    pub name: Expr,
+    pub scope: Rc<Scope>,
+}
+
+pub struct Macro {
+    pub name: String,
+    pub transformer: Transformer,
+}
+
+pub enum Transformer {
+    SyntaxRules(SyntaxRules),
+    /// A `define-macro` procedure, applied to the operands of each use.
+    Procedure(Expr),
+    /// An `er-macro-transformer` procedure, applied to each use along with procedures that
+    /// rename identifiers into the scope the macro was defined in and compare identifiers.
+    ExplicitRenaming(Expr, Rc<Scope>),
+}
+
+impl PartialEq for Alias {
+    fn eq(&self, other: &Alias) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Debug for Alias {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Display::fmt(self, f)
+    }
+}
+
+impl Display for Alias {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Display::fmt(&self.name, f)
+    }
+}
+
+impl PartialEq for Macro {
+    fn eq(&self, other: &Macro) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Debug for Macro {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Display::fmt(self, f)
+    }
+}
+
+impl Display for Macro {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "#<macro {}>", self.name)
+    }
+}
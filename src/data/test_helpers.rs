@@ -1,10 +1,12 @@
 #![cfg(test)]
 
+use std::cell::RefCell;
 use std::rc::Rc;
 
-use num::{BigInt, Num};
+use num::complex::{Complex, Complex64};
+use num::{BigInt, BigRational, Num};
 
-use crate::data::{Expr, Expression, Number};
+use crate::data::{Expr, Expression, Number, Primitive};
 
 pub fn integer<T: Into<BigInt>>(value: T) -> Number {
     Number::Integer(value.into())
@@ -15,22 +17,55 @@ pub fn integer_str<T: AsRef<str>>(s: T, radix: u32) -> Number {
     Number::Integer(value)
 }
 
+pub fn rational<T: Into<BigInt>>(numer: T, denom: T) -> Number {
+    Number::rational(BigRational::new(numer.into(), denom.into()))
+}
+
 pub fn real(value: f64) -> Number {
     Number::Real(value)
 }
 
+pub fn complex(re: f64, im: f64) -> Number {
+    Number::Complex(Complex64::new(re, im))
+}
+
+pub fn exact_complex(re: Number, im: Number) -> Number {
+    Number::ExactComplex(Complex::new(re.to_rational().unwrap(), im.to_rational().unwrap()))
+}
+
 pub fn nil() -> Expr {
     Rc::new(Expression::Nil)
 }
 
+pub fn boolean(value: bool) -> Expr {
+    Rc::new(Expression::Boolean(value))
+}
+
+pub fn character(value: char) -> Expr {
+    Rc::new(Expression::Char(value))
+}
+
 pub fn number(value: Number) -> Expr {
     Rc::new(Expression::Number(value))
 }
 
+pub fn string<T: ToString>(value: T) -> Expr {
+    Rc::new(Expression::String(RefCell::new(value.to_string())))
+}
+
 pub fn symbol<T: ToString>(name: T) -> Expr {
     Rc::new(Expression::Symbol(name.to_string()))
 }
 
+pub fn primitive(name: &'static str) -> Expr {
+    Rc::new(Expression::Primitive(Rc::new(Primitive {
+        name,
+        min_args: 0,
+        max_args: None,
+        func: Box::new(|_| Ok(nil())),
+    })))
+}
+
 pub fn pair(car: Expr, cdr: Expr) -> Expr {
     Rc::new(Expression::Pair(car, cdr))
 }
@@ -45,6 +80,14 @@ pub fn list(items: Vec<Expr>) -> Expr {
     head
 }
 
+pub fn vector(items: Vec<Expr>) -> Expr {
+    Rc::new(Expression::Vector(RefCell::new(items)))
+}
+
+pub fn bytevector(bytes: Vec<u8>) -> Expr {
+    Rc::new(Expression::Bytevector(RefCell::new(bytes)))
+}
+
 macro_rules! list {
     ($($items:expr),* $(,)?) => {
         list(vec![$($items),*])
@@ -0,0 +1,69 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::rc::Rc;
+use std::slice::Iter;
+use std::vec::IntoIter;
+
+use crate::data::{Expr, Expression};
+
+/// The values an expression returns. Most expressions return exactly one, which the evaluator
+/// passes around by itself; any other number of values travels as an `Expression::Values`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Values(Vec<Expr>);
+
+impl Values {
+    pub fn new(values: Vec<Expr>) -> Self {
+        Values(values)
+    }
+
+    /// Packs the values into the expression the evaluator passes around.
+    pub fn into_expr(mut self) -> Expr {
+        match self.0.len() {
+            1 => self.0.pop().unwrap(),
+            _ => Rc::new(Expression::Values(self)),
+        }
+    }
+
+    pub fn into_vec(self) -> Vec<Expr> {
+        self.0
+    }
+}
+
+/// Unpacks the values an expression stands for.
+impl From<Expr> for Values {
+    fn from(expr: Expr) -> Values {
+        match &*expr {
+            Expression::Values(values) => values.clone(),
+            _ => Values(vec![expr]),
+        }
+    }
+}
+
+impl IntoIterator for Values {
+    type Item = Expr;
+    type IntoIter = IntoIter<Expr>;
+
+    fn into_iter(self) -> IntoIter<Expr> {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Values {
+    type Item = &'a Expr;
+    type IntoIter = Iter<'a, Expr>;
+
+    fn into_iter(self) -> Iter<'a, Expr> {
+        self.0.iter()
+    }
+}
+
+impl Display for Values {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for (i, value) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", value)?;
+        }
+        Ok(())
+    }
+}
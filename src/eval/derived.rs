@@ -0,0 +1,420 @@
+use std::rc::Rc;
+
+use crate::data::{Control, Expr, Expression, Primitive};
+use crate::eval::expr_evaluator::{args, bad_syntax};
+use crate::eval::EvalResult;
+
+pub (crate) const KEYWORDS: &[&str] = &[
+    "let", "let*", "letrec", "letrec*", "and", "or", "when", "unless", "cond", "case", "do", "guard",
+    "let*-values", "receive",
+];
+
+/// Rewrites a derived expression into the core forms, or returns `None` if `keyword` does not
+/// name a derived form. Subexpressions supplied by the user are only ever evaluated in the
+/// environment of the original form, so the rewritten code cannot capture their variables.
+/// The expander renames the symbols the rewrite introduces, so they keep their meaning.
+/// `is_auxiliary` tells whether an identifier is the auxiliary keyword `else` or `=>`, rather
+/// than a local variable of the same name.
+pub (crate) fn expand(keyword: &str, form: &Expr, tail: &Expr, is_auxiliary: &dyn Fn(&Expr, &str) -> bool) -> Option<EvalResult<Expr>> {
+    let expansion = match keyword {
+        "let" => expand_let(form, tail),
+        "let*" | "let*-values" => expand_let_star(keyword, form, tail),
+        "letrec" | "letrec*" => expand_letrec(keyword, form, tail),
+        "and" => expand_and(tail),
+        "or" => expand_or(tail),
+        "when" => expand_when(form, tail, true),
+        "unless" => expand_when(form, tail, false),
+        "cond" => expand_cond(form, tail, is_auxiliary),
+        "case" => expand_case(form, tail, is_auxiliary),
+        "do" => expand_do(form, tail),
+        "guard" => expand_guard(form, tail, is_auxiliary),
+        "receive" => expand_receive(form, tail),
+        _ => return None,
+    };
+
+    Some(expansion)
+}
+
+fn expand_let(form: &Expr, tail: &Expr) -> EvalResult<Expr> {
+    let items = args(tail, "let")?;
+
+    match items.split_first() {
+        Some((name, rest)) if is_variable(name) => match rest.split_first() {
+            Some((bindings, body)) if !body.is_empty() => {
+                let (names, inits) = bindings_of("let", bindings)?;
+                let procedure = lambda(Expression::list(names), body.to_vec());
+                let letrec = list(vec![
+                    symbol("letrec"),
+                    list(vec![list(vec![name.clone(), procedure])]),
+                    name.clone(),
+                ]);
+
+                Ok(call(letrec, inits))
+            },
+            _ => Err(bad_syntax("let", form)),
+        },
+        Some((bindings, body)) if !body.is_empty() => {
+            let (names, inits) = bindings_of("let", bindings)?;
+            Ok(call(lambda(Expression::list(names), body.to_vec()), inits))
+        },
+        _ => Err(bad_syntax("let", form)),
+    }
+}
+
+/// Nests one `let`, or `let-values` for `let*-values`, per binding.
+fn expand_let_star(keyword: &str, form: &Expr, tail: &Expr) -> EvalResult<Expr> {
+    let items = args(tail, keyword)?;
+    let inner = if keyword == "let*" { "let" } else { "let-values" };
+
+    match items.split_first() {
+        Some((bindings, body)) if !body.is_empty() => {
+            let bindings = args(bindings, keyword)?;
+
+            let innermost = list(vec![symbol(inner), nil()].into_iter().chain(body.iter().cloned()).collect());
+            Ok(bindings.into_iter().rev().fold(innermost, |body, binding| {
+                list(vec![symbol(inner), list(vec![binding]), body])
+            }))
+        },
+        _ => Err(bad_syntax(keyword, form)),
+    }
+}
+
+/// Expands to a procedure body whose internal definitions are evaluated in order, which gives
+/// `letrec*` semantics; these are also valid for `letrec`.
+fn expand_letrec(keyword: &str, form: &Expr, tail: &Expr) -> EvalResult<Expr> {
+    let items = args(tail, keyword)?;
+
+    match items.split_first() {
+        Some((bindings, body)) if !body.is_empty() => {
+            let (names, inits) = bindings_of(keyword, bindings)?;
+            let mut forms = names.into_iter()
+                .zip(inits)
+                .map(|(name, init)| list(vec![symbol("define"), name, init]))
+                .collect::<Vec<_>>();
+            forms.push(list(vec![symbol("let"), nil()].into_iter().chain(body.iter().cloned()).collect()));
+
+            Ok(call(lambda(nil(), forms), vec![]))
+        },
+        _ => Err(bad_syntax(keyword, form)),
+    }
+}
+
+fn expand_and(tail: &Expr) -> EvalResult<Expr> {
+    let items = args(tail, "and")?;
+
+    match items.split_first() {
+        None => Ok(Rc::new(Expression::Boolean(true))),
+        Some((only, [])) => Ok(only.clone()),
+        Some((first, rest)) => {
+            let rest = list(std::iter::once(symbol("and")).chain(rest.iter().cloned()).collect());
+            Ok(list(vec![symbol("if"), first.clone(), rest, Rc::new(Expression::Boolean(false))]))
+        },
+    }
+}
+
+fn expand_or(tail: &Expr) -> EvalResult<Expr> {
+    let items = args(tail, "or")?;
+
+    match items.split_first() {
+        None => Ok(Rc::new(Expression::Boolean(false))),
+        Some((only, [])) => Ok(only.clone()),
+        Some((first, rest)) => {
+            let rest = list(std::iter::once(symbol("or")).chain(rest.iter().cloned()).collect());
+            Ok(first_true(first.clone(), rest))
+        },
+    }
+}
+
+fn expand_when(form: &Expr, tail: &Expr, when: bool) -> EvalResult<Expr> {
+    let keyword = if when { "when" } else { "unless" };
+    let items = args(tail, keyword)?;
+
+    match items.split_first() {
+        Some((test, body)) if !body.is_empty() => {
+            let body = begin(body.to_vec());
+            let (consequent, alternative) = if when { (body, unspecified()) } else { (unspecified(), body) };
+
+            Ok(list(vec![symbol("if"), test.clone(), consequent, alternative]))
+        },
+        _ => Err(bad_syntax(keyword, form)),
+    }
+}
+
+fn expand_cond(form: &Expr, tail: &Expr, is_auxiliary: &dyn Fn(&Expr, &str) -> bool) -> EvalResult<Expr> {
+    let clauses = args(tail, "cond")?;
+    let mut expansion = unspecified();
+
+    for (i, clause) in clauses.iter().enumerate().rev() {
+        let items = match clause.list_to_vec() {
+            Some(items) if !items.is_empty() => items,
+            _ => return Err(bad_syntax("cond clause", clause)),
+        };
+
+        expansion = match items.as_slice() {
+            [test, body @ ..] if is_auxiliary(test, "else") => {
+                if body.is_empty() || i != clauses.len() - 1 {
+                    return Err(bad_syntax("cond", form));
+                }
+                begin(body.to_vec())
+            },
+            [test] => first_true(test.clone(), expansion),
+            [test, arrow, receiver] if is_auxiliary(arrow, "=>") => {
+                // ((lambda (t r k) (if t ((r) t) (k))) test (lambda () receiver) (lambda () rest))
+                let procedure = lambda(
+                    list(vec![symbol("t"), symbol("r"), symbol("k")]),
+                    vec![list(vec![
+                        symbol("if"),
+                        symbol("t"),
+                        list(vec![list(vec![symbol("r")]), symbol("t")]),
+                        list(vec![symbol("k")]),
+                    ])],
+                );
+                call(procedure, vec![test.clone(), thunk(vec![receiver.clone()]), thunk(vec![expansion])])
+            },
+            [_, arrow, ..] if is_auxiliary(arrow, "=>") => return Err(bad_syntax("cond clause", clause)),
+            [test, body @ ..] => list(vec![symbol("if"), test.clone(), begin(body.to_vec()), expansion]),
+            [] => unreachable!(),
+        };
+    }
+
+    Ok(expansion)
+}
+
+/// Expands to a dispatch procedure that receives the key and one thunk per clause, so that
+/// clause bodies are evaluated in the environment of the `case` form.
+fn expand_case(form: &Expr, tail: &Expr, is_auxiliary: &dyn Fn(&Expr, &str) -> bool) -> EvalResult<Expr> {
+    let items = args(tail, "case")?;
+    let (key, clauses) = match items.split_first() {
+        Some((key, clauses)) => (key, clauses),
+        None => return Err(bad_syntax("case", form)),
+    };
+
+    let mut params = vec![symbol("key")];
+    let mut thunks = vec![];
+    let mut dispatch = unspecified();
+
+    for (i, clause) in clauses.iter().enumerate().rev() {
+        let items = match clause.list_to_vec() {
+            Some(items) if items.len() >= 2 => items,
+            _ => return Err(bad_syntax("case clause", clause)),
+        };
+
+        let name = symbol(&format!("clause{}", i));
+        let (data, body) = items.split_first().unwrap();
+        let consequent = match body {
+            [arrow, receiver] if is_auxiliary(arrow, "=>") => {
+                thunks.push(thunk(vec![receiver.clone()]));
+                list(vec![list(vec![name.clone()]), symbol("key")])
+            },
+            [arrow, ..] if is_auxiliary(arrow, "=>") => return Err(bad_syntax("case clause", clause)),
+            _ => {
+                thunks.push(thunk(body.to_vec()));
+                list(vec![name.clone()])
+            },
+        };
+        params.push(name);
+
+        dispatch = if is_auxiliary(data, "else") {
+            if i != clauses.len() - 1 {
+                return Err(bad_syntax("case", form));
+            }
+            consequent
+        } else if data.list_to_vec().is_some() {
+            let test = list(vec![memv(), symbol("key"), quote(data.clone())]);
+            list(vec![symbol("if"), test, consequent, dispatch])
+        } else {
+            return Err(bad_syntax("case clause", clause));
+        };
+    }
+
+    let operands = std::iter::once(key.clone()).chain(thunks.into_iter().rev()).collect();
+    params[1..].reverse();
+
+    Ok(call(lambda(Expression::list(params), vec![dispatch]), operands))
+}
+
+fn expand_do(form: &Expr, tail: &Expr) -> EvalResult<Expr> {
+    let items = args(tail, "do")?;
+    let (specs, exit, commands) = match items.as_slice() {
+        [specs, exit, commands @ ..] => (specs, exit, commands),
+        _ => return Err(bad_syntax("do", form)),
+    };
+
+    let mut bindings = vec![];
+    let mut steps = vec![];
+    for spec in args(specs, "do")? {
+        match spec.list_to_vec().as_deref() {
+            Some([name, init]) if is_variable(name) => {
+                bindings.push(list(vec![name.clone(), init.clone()]));
+                steps.push(name.clone());
+            },
+            Some([name, init, step]) if is_variable(name) => {
+                bindings.push(list(vec![name.clone(), init.clone()]));
+                steps.push(step.clone());
+            },
+            _ => return Err(bad_syntax("do", &spec)),
+        }
+    }
+
+    let (test, results) = match exit.list_to_vec() {
+        Some(items) if !items.is_empty() => (items[0].clone(), items[1..].to_vec()),
+        _ => return Err(bad_syntax("do", exit)),
+    };
+
+    let name = symbol("loop");
+    let mut body = commands.to_vec();
+    body.push(call(name.clone(), steps));
+    let result = if results.is_empty() { unspecified() } else { begin(results) };
+    let loop_body = list(vec![symbol("if"), test, result, begin(body)]);
+
+    Ok(list(vec![symbol("let"), name, list(bindings), loop_body]))
+}
+
+/// Rewrites `(guard (var clause ...) body ...)` as in R7RS: the body runs with a handler that
+/// jumps back to the guard to evaluate the clauses like `cond`. If no clause applies, the
+/// condition is raised again from the dynamic environment of the original raise.
+fn expand_guard(form: &Expr, tail: &Expr, is_auxiliary: &dyn Fn(&Expr, &str) -> bool) -> EvalResult<Expr> {
+    let items = args(tail, "guard")?;
+    let (spec, body) = match items.split_first() {
+        Some((spec, body)) if !body.is_empty() => (spec, body),
+        _ => return Err(bad_syntax("guard", form)),
+    };
+
+    let (var, mut clauses) = match spec.list_to_vec().as_deref() {
+        Some([var, clauses @ ..]) if is_variable(var) => (var.clone(), clauses.to_vec()),
+        _ => return Err(bad_syntax("guard", spec)),
+    };
+
+    let has_else = matches!(clauses.last().map(|c| &**c), Some(Expression::Pair(head, _)) if is_auxiliary(head, "else"));
+    if !has_else {
+        let reraise = call(control(Control::RaiseContinuable), vec![symbol("condition")]);
+        clauses.push(list(vec![symbol("else"), call(symbol("handler-k"), vec![thunk(vec![reraise])])]));
+    }
+
+    let cond = list(std::iter::once(symbol("cond")).chain(clauses).collect());
+    let handle = list(vec![symbol("let"), list(vec![list(vec![var, symbol("condition")])]), cond]);
+    let reenter = lambda(list(vec![symbol("handler-k")]), vec![call(symbol("guard-k"), vec![thunk(vec![handle])])]);
+    let handler = lambda(
+        list(vec![symbol("condition")]),
+        vec![call(call(control(Control::CallWithCurrentContinuation), vec![reenter]), vec![])],
+    );
+
+    let result = list(vec![symbol("let"), nil()].into_iter().chain(body.iter().cloned()).collect());
+    let leave = list(vec![symbol("let"), list(vec![list(vec![symbol("result"), result])]), thunk(vec![symbol("result")])]);
+    let protected = thunk(vec![call(symbol("guard-k"), vec![leave])]);
+
+    let install = call(control(Control::WithExceptionHandler), vec![handler, protected]);
+    let escape = lambda(list(vec![symbol("guard-k")]), vec![install]);
+
+    Ok(call(call(control(Control::CallWithCurrentContinuation), vec![escape]), vec![]))
+}
+
+fn expand_receive(form: &Expr, tail: &Expr) -> EvalResult<Expr> {
+    let items = args(tail, "receive")?;
+
+    match items.as_slice() {
+        [formals, init, body @ ..] if !body.is_empty() => {
+            let bindings = list(vec![list(vec![formals.clone(), init.clone()])]);
+            Ok(list(vec![symbol("let-values"), bindings].into_iter().chain(body.iter().cloned()).collect()))
+        },
+        _ => Err(bad_syntax("receive", form)),
+    }
+}
+
+/// Splits `((name init) ...)` into its names and initial expressions.
+fn bindings_of(keyword: &str, bindings: &Expr) -> EvalResult<(Vec<Expr>, Vec<Expr>)> {
+    let mut names = vec![];
+    let mut inits = vec![];
+
+    for binding in args(bindings, keyword)? {
+        match binding.list_to_vec().as_deref() {
+            Some([name, init]) if is_variable(name) => {
+                names.push(name.clone());
+                inits.push(init.clone());
+            },
+            _ => return Err(bad_syntax(keyword, &binding)),
+        }
+    }
+
+    Ok((names, inits))
+}
+
+/// Builds `((lambda (t k) (if t t (k))) value (lambda () otherwise))`, which yields `value` if it
+/// is true and evaluates `otherwise` in tail position if not.
+fn first_true(value: Expr, otherwise: Expr) -> Expr {
+    let procedure = lambda(
+        list(vec![symbol("t"), symbol("k")]),
+        vec![list(vec![symbol("if"), symbol("t"), symbol("t"), list(vec![symbol("k")])])],
+    );
+
+    call(procedure, vec![value, thunk(vec![otherwise])])
+}
+
+/// The `memv` procedure, referenced directly so that `case` works even if `memv` is rebound.
+fn memv() -> Expr {
+    fn memv(args: &[Expr]) -> EvalResult<Expr> {
+        let mut tail = &args[1];
+
+        while let Expression::Pair(head, rest) = &**tail {
+            if args[0].eqv(head) {
+                return Ok(tail.clone());
+            }
+            tail = rest;
+        }
+
+        Ok(Rc::new(Expression::Boolean(false)))
+    }
+
+    Rc::new(Expression::Primitive(Rc::new(Primitive {
+        name: "memv",
+        min_args: 2,
+        max_args: Some(2),
+        func: Box::new(memv),
+    })))
+}
+
+/// A procedure the evaluator implements, referenced directly so that `guard` works even if its
+/// global name is rebound.
+fn control(control: Control) -> Expr {
+    Rc::new(Expression::Control(control))
+}
+
+fn is_variable(expr: &Expr) -> bool {
+    expr.is_identifier()
+}
+
+fn symbol(name: &str) -> Expr {
+    Rc::new(Expression::Symbol(name.to_string()))
+}
+
+fn nil() -> Expr {
+    Rc::new(Expression::Nil)
+}
+
+fn list(items: Vec<Expr>) -> Expr {
+    Expression::list(items)
+}
+
+fn quote(datum: Expr) -> Expr {
+    list(vec![symbol("quote"), datum])
+}
+
+fn call(operator: Expr, operands: Vec<Expr>) -> Expr {
+    list(std::iter::once(operator).chain(operands).collect())
+}
+
+fn lambda(params: Expr, body: Vec<Expr>) -> Expr {
+    list(vec![symbol("lambda"), params].into_iter().chain(body).collect())
+}
+
+fn thunk(body: Vec<Expr>) -> Expr {
+    lambda(nil(), body)
+}
+
+fn begin(body: Vec<Expr>) -> Expr {
+    list(std::iter::once(symbol("begin")).chain(body).collect())
+}
+
+fn unspecified() -> Expr {
+    list(vec![symbol("if"), Rc::new(Expression::Boolean(false)), Rc::new(Expression::Boolean(false))])
+}
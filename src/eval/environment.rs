@@ -0,0 +1,59 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::rc::Rc;
+
+use crate::data::Expr;
+
+pub struct Environment {
+    bindings: RefCell<HashMap<String, Expr>>,
+    parent: Option<Env>,
+}
+
+pub type Env = Rc<Environment>;
+
+impl Environment {
+    pub fn new() -> Env {
+        Rc::new(Environment {
+            bindings: RefCell::new(HashMap::new()),
+            parent: None,
+        })
+    }
+
+    pub fn extend(parent: &Env) -> Env {
+        Rc::new(Environment {
+            bindings: RefCell::new(HashMap::new()),
+            parent: Some(parent.clone()),
+        })
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<Expr> {
+        if let Some(value) = self.bindings.borrow().get(name) {
+            return Some(value.clone());
+        }
+
+        self.parent.as_ref().and_then(|p| p.lookup(name))
+    }
+
+    pub fn define<T: ToString>(&self, name: T, value: Expr) {
+        self.bindings.borrow_mut().insert(name.to_string(), value);
+    }
+
+    pub fn set(&self, name: &str, value: Expr) -> bool {
+        if let Some(slot) = self.bindings.borrow_mut().get_mut(name) {
+            *slot = value;
+            return true;
+        }
+
+        match &self.parent {
+            Some(p) => p.set(name, value),
+            None => false,
+        }
+    }
+}
+
+impl Debug for Environment {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "#<environment>")
+    }
+}
@@ -1,29 +1,103 @@
 use std::error::Error;
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
-#[derive(Debug)]
-pub enum EvalError {
-    Custom(Box<dyn Error>),
+use crate::data::{span_of, Expr, Expression, Span};
+
+#[derive(Clone, Debug)]
+pub struct EvalError {
+    pub kind: EvalErrorKind,
+    /// The innermost expression being evaluated when the error occurred.
+    pub expr: Option<Expr>,
+}
+
+#[derive(Clone, Debug)]
+pub enum EvalErrorKind {
+    UnboundVariable(String),
+    WrongType { procedure: String, expected: String, actual: Expr },
+    Arity { procedure: Expr, min_args: usize, max_args: Option<usize>, actual: usize },
+    NotProcedure(Expr),
+    DivisionByZero(String),
+    IndexOutOfRange { procedure: String, index: usize, length: usize },
+    BadSyntax { context: String, form: Expr },
+    Raise(Expr),
+    /// The handler for a non-continuable `raise` of the object returned.
+    NonContinuable(Expr),
+    UnknownCommand(String),
 }
 
 pub type EvalResult<T> = Result<T, EvalError>;
 
+impl EvalError {
+    pub fn new(kind: EvalErrorKind) -> EvalError {
+        EvalError { kind, expr: None }
+    }
+
+    /// Records the expression being evaluated, unless the error has already been located.
+    pub fn at(mut self, expr: &Expr) -> EvalError {
+        if self.span().is_none() {
+            self.expr = Some(expr.clone());
+        }
+
+        self
+    }
+
+    /// Finds where in the source the error occurred, if the expression came from the reader.
+    pub fn span(&self) -> Option<Span> {
+        self.expr.as_ref().and_then(span_of)
+    }
+}
+
+impl From<EvalErrorKind> for EvalError {
+    fn from(kind: EvalErrorKind) -> EvalError {
+        EvalError::new(kind)
+    }
+}
+
 impl Display for EvalError {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        use EvalError::*;
+        self.kind.fmt(f)?;
 
-        match self {
-            Custom(e) => e.fmt(f),
+        if let Some(span) = self.span() {
+            write!(f, " (at {})", span)?;
         }
+
+        Ok(())
     }
 }
 
-impl Error for EvalError {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        use EvalError::*;
+impl Display for EvalErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use EvalErrorKind::*;
 
         match self {
-            Custom(e) => e.source(),
+            UnboundVariable(name) => write!(f, "unbound variable: {}", name),
+            WrongType { procedure, expected, actual } => write!(f, "{}: expected {}, got {}", procedure, expected, actual),
+            Arity { procedure, min_args, max_args, actual } => {
+                write!(f, "{}: wrong number of arguments: expected ", procedure)?;
+                match max_args {
+                    Some(max) if max == min_args => write!(f, "{}", min_args)?,
+                    Some(max) => write!(f, "{} to {}", min_args, max)?,
+                    None => write!(f, "{} or more", min_args)?,
+                }
+                write!(f, ", got {}", actual)
+            },
+            NotProcedure(value) => write!(f, "not a procedure: {}", value),
+            DivisionByZero(procedure) => write!(f, "{}: division by zero", procedure),
+            IndexOutOfRange { procedure, index, length } => {
+                write!(f, "{}: index {} out of range for length {}", procedure, index, length)
+            },
+            BadSyntax { context, form } => write!(f, "bad syntax in {}: {}", context, form),
+            Raise(payload) => match &**payload {
+                Expression::ErrorObject(error) => {
+                    write!(f, "{}", error.message)?;
+                    error.irritants.iter().try_for_each(|irritant| write!(f, " {}", irritant))
+                },
+                _ => write!(f, "uncaught exception: {}", payload),
+            },
+            NonContinuable(payload) => write!(f, "exception handler returned from non-continuable raise: {}", payload),
+            UnknownCommand(name) => write!(f, "unknown command: ,{}", name),
         }
     }
 }
+
+impl Error for EvalError {}
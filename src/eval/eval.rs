@@ -1,7 +1,17 @@
-use crate::eval::EvalResult;
+use crate::eval::{EvalErrorKind, EvalResult};
 
 pub trait Eval<T> {
     type Output;
 
     fn eval(&mut self, value: T) -> EvalResult<Self::Output>;
+
+    /// Returns the name of the REPL command that `value` invokes, if it is one. A command
+    /// operates on the next value read instead of evaluating it.
+    fn command(&self, _value: &T) -> Option<String> {
+        None
+    }
+
+    fn run_command(&mut self, name: &str, _operand: T) -> EvalResult<Self::Output> {
+        Err(EvalErrorKind::UnknownCommand(name.to_string()).into())
+    }
 }
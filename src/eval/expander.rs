@@ -0,0 +1,865 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashSet, VecDeque};
+use std::rc::Rc;
+
+use crate::data::{set_span, span_of, Alias, Expr, Expression, Macro, Primitive, Transformer};
+use crate::eval::expr_evaluator::{args, bad_syntax, call, eval};
+use crate::eval::{derived, Env, EvalErrorKind, EvalResult, SyntaxRules};
+
+/// The keywords of the forms the expander knows how to expand.
+const KEYWORDS: &[&str] = &[
+    "quote", "quasiquote", "if", "define", "define-values", "set!", "lambda", "begin", "let-values",
+    "define-syntax", "let-syntax", "letrec-syntax", "syntax-rules", "define-macro", "er-macro-transformer",
+];
+
+/// The keywords the evaluator recognizes in expanded code, which local variables must not use
+/// as names.
+const CORE_FORMS: &[&str] = &[
+    "quote", "quasiquote", "unquote", "unquote-splicing", "if", "define", "define-values", "set!", "lambda", "begin",
+    "let-values",
+];
+
+/// The compile-time bindings of one lexical scope: which identifiers name local variables and
+/// which name macros. Top-level bindings live in the global environment instead.
+pub struct Scope {
+    bindings: RefCell<Vec<(Expr, Binding)>>,
+    parent: Option<Rc<Scope>>,
+}
+
+#[derive(Clone)]
+enum Binding {
+    Variable(Rc<Variable>),
+    Macro(Rc<Macro>),
+}
+
+/// A local variable. It keeps its name in the expanded code unless another binding with the
+/// same name would capture one of the references to it, or to a global, in which case it is
+/// renamed.
+struct Variable {
+    name: String,
+    id: usize,
+    renamed: Cell<bool>,
+}
+
+enum Meaning {
+    Variable(Rc<Variable>),
+    Macro(Rc<Macro>),
+    Keyword(String),
+    Global(String),
+}
+
+struct Expander<'a> {
+    env: &'a Env,
+    variables: Vec<Rc<Variable>>,
+}
+
+/// The definitions collected from a body, which are expanded after all of them are known.
+enum BodyForm {
+    Define(Expr, Definition, Expr),
+    DefineValues(Expr, Expr, Expr),
+    Expression(Expr),
+}
+
+enum Definition {
+    Value(Option<Expr>),
+    Procedure(Expr, Expr),
+}
+
+/// Expands all macros and derived forms in a top-level form, leaving only the core forms the
+/// evaluator understands.
+pub (crate) fn expand(form: &Expr, env: &Env) -> EvalResult<Expr> {
+    let mut expander = Expander { env, variables: Vec::new() };
+    let expanded = expander.expand_toplevel(form, &Scope::root())?;
+
+    Ok(expander.finish(&expanded))
+}
+
+/// Expands the macro use or derived form at the head of a form once, or returns the form
+/// unchanged if it is neither.
+pub (crate) fn expand_once(form: &Expr, env: &Env) -> EvalResult<Expr> {
+    let expander = Expander { env, variables: Vec::new() };
+    let scope = Scope::root();
+
+    let expansion = match (expander.head_meaning(form, &scope), &**form) {
+        (Some(Meaning::Macro(m)), _) => expander.transcribe(&m, form, &scope),
+        (Some(Meaning::Keyword(keyword)), Expression::Pair(_, tail)) => {
+            let is_auxiliary = |identifier: &Expr, name: &str| expander.is_auxiliary(identifier, name, &scope);
+            derived::expand(&keyword, form, tail, &is_auxiliary).unwrap_or_else(|| Ok(form.clone()))
+        },
+        _ => Ok(form.clone()),
+    };
+
+    expansion.map(|e| strip_syntax(&e)).map_err(|err| err.at(form))
+}
+
+/// Defines the `expand` and `expand-once` procedures, which expand a datum using the macros
+/// defined in `env`.
+pub (crate) fn register(env: &Env) {
+    fn define(env: &Env, name: &'static str, expand: fn(&Expr, &Env) -> EvalResult<Expr>) {
+        // The environment holds the procedure, so the procedure only holds it weakly.
+        let weak = Rc::downgrade(env);
+        let func = move |args: &[Expr]| expand(&args[0], &weak.upgrade().unwrap());
+
+        let primitive = Primitive { name, min_args: 1, max_args: Some(1), func: Box::new(func) };
+        env.define(name, Rc::new(Expression::Primitive(Rc::new(primitive))));
+    }
+
+    define(env, "expand", expand);
+    define(env, "expand-once", expand_once);
+}
+
+/// Tests whether two identifiers are the same: symbols by name, aliases by identity.
+pub (crate) fn same_identifier(a: &Expr, b: &Expr) -> bool {
+    match (&**a, &**b) {
+        (Expression::Symbol(a), Expression::Symbol(b)) => a == b,
+        (Expression::Alias(a), Expression::Alias(b)) => Rc::ptr_eq(a, b),
+        _ => false,
+    }
+}
+
+/// Replaces aliases in a datum with the symbols they were renamed from.
+pub (crate) fn strip_syntax(datum: &Expr) -> Expr {
+    map_identifiers(datum, &|id| match &**id {
+        Expression::Alias(_) => Some(Rc::new(Expression::Symbol(id.identifier_name().unwrap().to_string()))),
+        _ => None,
+    }).unwrap_or_else(|| datum.clone())
+}
+
+impl Scope {
+    fn root() -> Rc<Scope> {
+        Rc::new(Scope { bindings: RefCell::new(Vec::new()), parent: None })
+    }
+
+    fn extend(parent: &Rc<Scope>) -> Rc<Scope> {
+        Rc::new(Scope { bindings: RefCell::new(Vec::new()), parent: Some(parent.clone()) })
+    }
+
+    fn find(&self, identifier: &Expr) -> Option<Binding> {
+        self.bindings.borrow().iter()
+            .rev()
+            .find(|(id, _)| same_identifier(id, identifier))
+            .map(|(_, binding)| binding.clone())
+    }
+
+    fn bind(&self, identifier: &Expr, binding: Binding) {
+        self.bindings.borrow_mut().push((identifier.clone(), binding));
+    }
+}
+
+impl<'a> Expander<'a> {
+    fn resolve(&self, identifier: &Expr, scope: &Rc<Scope>) -> Meaning {
+        let mut current = Some(scope);
+
+        while let Some(scope) = current {
+            match scope.find(identifier) {
+                Some(Binding::Variable(variable)) => return Meaning::Variable(variable),
+                Some(Binding::Macro(m)) => return Meaning::Macro(m),
+                None => current = scope.parent.as_ref(),
+            }
+        }
+
+        match &**identifier {
+            Expression::Alias(alias) => self.resolve(&alias.name, &alias.scope),
+            _ => {
+                let name = identifier.identifier_name().unwrap();
+
+                match self.env.lookup(name).as_deref() {
+                    Some(Expression::Macro(m)) => Meaning::Macro(m.clone()),
+                    _ if is_keyword(name) => Meaning::Keyword(name.to_string()),
+                    _ => Meaning::Global(name.to_string()),
+                }
+            },
+        }
+    }
+
+    /// Tests whether two identifiers, each in its own scope, refer to the same binding.
+    fn same_meaning(&self, a: &Expr, a_scope: &Rc<Scope>, b: &Expr, b_scope: &Rc<Scope>) -> bool {
+        match (self.resolve(a, a_scope), self.resolve(b, b_scope)) {
+            (Meaning::Variable(a), Meaning::Variable(b)) => Rc::ptr_eq(&a, &b),
+            (Meaning::Macro(a), Meaning::Macro(b)) => Rc::ptr_eq(&a, &b),
+            (Meaning::Keyword(a), Meaning::Keyword(b)) | (Meaning::Global(a), Meaning::Global(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Tests whether an identifier is the auxiliary keyword `name`, such as `else`: whether it
+    /// refers to the global binding of that name rather than a local one.
+    fn is_auxiliary(&self, identifier: &Expr, name: &str, scope: &Rc<Scope>) -> bool {
+        identifier.is_identifier() && matches!(self.resolve(identifier, scope), Meaning::Global(global) if global == name)
+    }
+
+    /// Returns the keyword or macro at the head of a form, if there is one.
+    fn head_meaning(&self, form: &Expr, scope: &Rc<Scope>) -> Option<Meaning> {
+        match &**form {
+            Expression::Pair(head, _) if head.is_identifier() => match self.resolve(head, scope) {
+                meaning @ Meaning::Keyword(_) | meaning @ Meaning::Macro(_) => Some(meaning),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn expand_toplevel(&mut self, form: &Expr, scope: &Rc<Scope>) -> EvalResult<Expr> {
+        self.expand_toplevel_inner(form, scope).map(|e| located(e, form)).map_err(|err| err.at(form))
+    }
+
+    fn expand_toplevel_inner(&mut self, form: &Expr, scope: &Rc<Scope>) -> EvalResult<Expr> {
+        let form = self.expand_macro_uses(form, scope)?;
+
+        match self.head_meaning(&form, scope) {
+            Some(Meaning::Keyword(keyword)) if keyword == "begin" => {
+                let forms = args(&form, "begin")?[1..].iter()
+                    .map(|f| self.expand_toplevel(f, scope))
+                    .collect::<EvalResult<Vec<_>>>()?;
+                Ok(keyword_form("begin", forms))
+            },
+            Some(Meaning::Keyword(keyword)) if keyword == "define" => {
+                let (target, definition) = parse_define(&form, "define")?;
+                let name = Rc::new(Expression::Symbol(target.identifier_name().unwrap().to_string()));
+                self.expand_definition(name, definition, scope)
+            },
+            Some(Meaning::Keyword(keyword)) if keyword == "define-values" => {
+                let (formals, init) = parse_define_values(&form)?;
+                Ok(keyword_form("define-values", vec![strip_syntax(&formals), self.expand_expr(&init, scope)?]))
+            },
+            Some(Meaning::Keyword(keyword)) if keyword == "define-syntax" || keyword == "define-macro" => {
+                // The macro is bound in the expansion's own scope for the forms after it, and
+                // only defined globally when the expansion is evaluated.
+                let (name, m) = self.define_syntax(&keyword, &form, scope)?;
+                scope.bind(&name, Binding::Macro(m.clone()));
+                let name = Rc::new(Expression::Symbol(name.identifier_name().unwrap().to_string()));
+                Ok(keyword_form("define", vec![name, Rc::new(Expression::Macro(m))]))
+            },
+            _ => self.expand_expr(&form, scope),
+        }
+    }
+
+    fn expand_expr(&mut self, form: &Expr, scope: &Rc<Scope>) -> EvalResult<Expr> {
+        self.expand_expr_inner(form, scope).map(|e| located(e, form)).map_err(|err| err.at(form))
+    }
+
+    fn expand_expr_inner(&mut self, form: &Expr, scope: &Rc<Scope>) -> EvalResult<Expr> {
+        use Expression::*;
+
+        match &**form {
+            Symbol(_) | Alias(_) => self.expand_reference(form, scope),
+            Pair(head, tail) => {
+                match self.head_meaning(form, scope) {
+                    Some(Meaning::Macro(m)) => {
+                        let expansion = self.transcribe(&m, form, scope)?;
+                        return self.expand_expr(&expansion, scope);
+                    },
+                    Some(Meaning::Keyword(keyword)) => return self.expand_keyword(&keyword, form, tail, scope),
+                    _ => {},
+                }
+
+                let mut items = vec![self.expand_expr(head, scope)?];
+                for operand in args(tail, "procedure call")? {
+                    items.push(self.expand_expr(&operand, scope)?);
+                }
+                Ok(Expression::list(items))
+            },
+            Nil => Err(bad_syntax("procedure call", form)),
+            Vector(_) => Ok(strip_syntax(form)),
+            _ => Ok(form.clone()),
+        }
+    }
+
+    fn expand_keyword(&mut self, keyword: &str, form: &Expr, tail: &Expr, scope: &Rc<Scope>) -> EvalResult<Expr> {
+        match keyword {
+            "quote" => match args(tail, "quote")?.as_slice() {
+                [datum] => Ok(keyword_form("quote", vec![strip_syntax(datum)])),
+                _ => Err(bad_syntax("quote", tail)),
+            },
+            "quasiquote" => match args(tail, "quasiquote")?.as_slice() {
+                [template] => Ok(keyword_form("quasiquote", vec![self.expand_quasiquote(template, 1, scope)?])),
+                _ => Err(bad_syntax("quasiquote", tail)),
+            },
+            "if" => match args(tail, "if")?.as_slice() {
+                items @ [_, _] | items @ [_, _, _] => {
+                    let items = items.iter().map(|e| self.expand_expr(e, scope)).collect::<EvalResult<_>>()?;
+                    Ok(keyword_form("if", items))
+                },
+                _ => Err(bad_syntax("if", tail)),
+            },
+            "set!" => match args(tail, "set!")?.as_slice() {
+                [target, value] if target.is_identifier() => {
+                    let target = match self.resolve(target, scope) {
+                        Meaning::Variable(_) | Meaning::Global(_) => self.expand_reference(target, scope)?,
+                        _ => return Err(bad_syntax("set!", tail)),
+                    };
+                    Ok(keyword_form("set!", vec![target, self.expand_expr(value, scope)?]))
+                },
+                _ => Err(bad_syntax("set!", tail)),
+            },
+            "lambda" => match &**tail {
+                Expression::Pair(params, body) => self.expand_lambda(params, body, scope),
+                _ => Err(bad_syntax("lambda", tail)),
+            },
+            "begin" => {
+                let items = args(tail, "begin")?.iter()
+                    .map(|e| self.expand_expr(e, scope))
+                    .collect::<EvalResult<_>>()?;
+                Ok(keyword_form("begin", items))
+            },
+            "let-values" => self.expand_let_values(form, tail, scope),
+            "let-syntax" | "letrec-syntax" => self.expand_let_syntax(keyword, form, tail, scope),
+            "define" | "define-values" | "define-syntax" | "define-macro" => Err(bad_syntax("expression context", form)),
+            _ => match derived::expand(keyword, form, tail, &|identifier, name| self.is_auxiliary(identifier, name, scope)) {
+                Some(expansion) => {
+                    let expansion = close_introduced(&expansion?, form);
+                    self.expand_expr(&expansion, scope)
+                },
+                None => Err(bad_syntax(keyword, form)),
+            },
+        }
+    }
+
+    /// Expands a variable reference, renaming any local variables that would capture it.
+    fn expand_reference(&mut self, identifier: &Expr, scope: &Rc<Scope>) -> EvalResult<Expr> {
+        match self.resolve(identifier, scope) {
+            Meaning::Variable(variable) => {
+                mark_shadowing(scope, &variable.name, Some(&variable));
+                Ok(located(variable_key(&variable), identifier))
+            },
+            Meaning::Global(name) => {
+                mark_shadowing(scope, &name, None);
+                match &**identifier {
+                    Expression::Symbol(_) => Ok(identifier.clone()),
+                    _ => Ok(located(Rc::new(Expression::Symbol(name)), identifier)),
+                }
+            },
+            _ => Err(bad_syntax("variable reference", identifier)),
+        }
+    }
+
+    fn expand_lambda(&mut self, params: &Expr, body: &Expr, scope: &Rc<Scope>) -> EvalResult<Expr> {
+        let inner = Scope::extend(scope);
+        let params = self.bind_params(params, &inner)?;
+
+        let forms = args(body, "lambda")?;
+        if forms.is_empty() {
+            return Err(bad_syntax("lambda", body));
+        }
+
+        let mut items = vec![params];
+        items.extend(self.expand_body(forms, &inner)?);
+        Ok(keyword_form("lambda", items))
+    }
+
+    fn bind_params(&mut self, params: &Expr, scope: &Rc<Scope>) -> EvalResult<Expr> {
+        use Expression::*;
+
+        match &**params {
+            Nil => Ok(params.clone()),
+            Symbol(_) | Alias(_) => Ok(self.bind_variable(params, scope)),
+            Pair(head, tail) if head.is_identifier() => {
+                let head = self.bind_variable(head, scope);
+                let tail = self.bind_params(tail, scope).map_err(|_| bad_syntax("parameter list", params))?;
+                Ok(Rc::new(Pair(head, tail)))
+            },
+            _ => Err(bad_syntax("parameter list", params)),
+        }
+    }
+
+    fn bind_variable(&mut self, identifier: &Expr, scope: &Rc<Scope>) -> Expr {
+        if let Some(Binding::Variable(variable)) = scope.find(identifier) {
+            return located(variable_key(&variable), identifier);
+        }
+
+        let variable = Rc::new(Variable {
+            name: identifier.identifier_name().unwrap().to_string(),
+            id: self.variables.len(),
+            renamed: Cell::new(false),
+        });
+        self.variables.push(variable.clone());
+        scope.bind(identifier, Binding::Variable(variable.clone()));
+
+        located(variable_key(&variable), identifier)
+    }
+
+    /// Expands a body, whose definitions are all bound in `scope` before any of the forms are
+    /// expanded so that they can refer to each other.
+    fn expand_body(&mut self, forms: Vec<Expr>, scope: &Rc<Scope>) -> EvalResult<Vec<Expr>> {
+        let mut queue = VecDeque::from(forms);
+        let mut body = Vec::new();
+
+        while let Some(form) = queue.pop_front() {
+            let form = self.expand_macro_uses(&form, scope).map_err(|err| err.at(&form))?;
+
+            match self.head_meaning(&form, scope) {
+                Some(Meaning::Keyword(keyword)) if keyword == "begin" => {
+                    for f in args(&form, "begin")?[1..].iter().rev() {
+                        queue.push_front(f.clone());
+                    }
+                },
+                Some(Meaning::Keyword(keyword)) if keyword == "define" => {
+                    let (target, definition) = parse_define(&form, "define").map_err(|err| err.at(&form))?;
+                    let target = self.bind_variable(&target, scope);
+                    body.push(BodyForm::Define(target, definition, form));
+                },
+                Some(Meaning::Keyword(keyword)) if keyword == "define-values" => {
+                    let (formals, init) = parse_define_values(&form).map_err(|err| err.at(&form))?;
+                    let formals = self.bind_params(&formals, scope)?;
+                    body.push(BodyForm::DefineValues(formals, init, form));
+                },
+                Some(Meaning::Keyword(keyword)) if keyword == "define-syntax" || keyword == "define-macro" => {
+                    let (name, m) = self.define_syntax(&keyword, &form, scope).map_err(|err| err.at(&form))?;
+                    scope.bind(&name, Binding::Macro(m));
+                },
+                _ => body.push(BodyForm::Expression(form)),
+            }
+        }
+
+        body.into_iter()
+            .map(|form| match form {
+                BodyForm::Define(target, definition, form) => self.expand_definition(target, definition, scope)
+                    .map(|e| located(e, &form))
+                    .map_err(|err| err.at(&form)),
+                BodyForm::DefineValues(formals, init, form) => self.expand_expr(&init, scope)
+                    .map(|init| located(keyword_form("define-values", vec![formals, init]), &form))
+                    .map_err(|err| err.at(&form)),
+                BodyForm::Expression(form) => self.expand_expr(&form, scope),
+            })
+            .collect()
+    }
+
+    fn expand_definition(&mut self, target: Expr, definition: Definition, scope: &Rc<Scope>) -> EvalResult<Expr> {
+        let value = match definition {
+            Definition::Value(None) => None,
+            Definition::Value(Some(value)) => Some(self.expand_expr(&value, scope)?),
+            Definition::Procedure(params, body) => Some(self.expand_lambda(&params, &body, scope)?),
+        };
+
+        Ok(keyword_form("define", std::iter::once(target).chain(value).collect()))
+    }
+
+    /// Expands macro uses at the head of a form until it is something else, so that bodies can
+    /// tell whether the form is a definition.
+    fn expand_macro_uses(&mut self, form: &Expr, scope: &Rc<Scope>) -> EvalResult<Expr> {
+        let mut form = form.clone();
+
+        while let Some(Meaning::Macro(m)) = self.head_meaning(&form, scope) {
+            form = self.transcribe(&m, &form, scope).map_err(|err| err.at(&form))?;
+        }
+
+        Ok(form)
+    }
+
+    fn transcribe(&self, m: &Macro, form: &Expr, scope: &Rc<Scope>) -> EvalResult<Expr> {
+        match &m.transformer {
+            Transformer::SyntaxRules(rules) => {
+                let is_literal = |literal: &Expr, input: &Expr| {
+                    self.same_meaning(literal, rules.scope(), input, scope)
+                };
+                rules.expand(&m.name, form, &is_literal)
+            },
+            Transformer::Procedure(procedure) => call(procedure, args(form, &m.name)?[1..].to_vec(), self.env),
+            Transformer::ExplicitRenaming(procedure, definition_scope) => {
+                call(procedure, vec![form.clone(), renamer(definition_scope), comparer(self.env, scope)], self.env)
+            },
+        }
+    }
+
+    fn define_syntax(&mut self, keyword: &str, form: &Expr, scope: &Rc<Scope>) -> EvalResult<(Expr, Rc<Macro>)> {
+        if keyword == "define-macro" {
+            return self.define_macro(form);
+        }
+
+        match args(form, "define-syntax")?.as_slice() {
+            [_, name, spec] if name.is_identifier() => {
+                let m = self.make_macro(name, spec, scope, scope)?;
+                Ok((name.clone(), m))
+            },
+            _ => Err(bad_syntax("define-syntax", form)),
+        }
+    }
+
+    /// Defines a non-hygienic macro from `(define-macro (name . params) body ...)` or
+    /// `(define-macro name procedure)`.
+    fn define_macro(&mut self, form: &Expr) -> EvalResult<(Expr, Rc<Macro>)> {
+        let (name, definition) = parse_define(form, "define-macro")?;
+
+        let procedure = match definition {
+            Definition::Procedure(params, body) => {
+                let lambda = Rc::new(Expression::Symbol("lambda".to_string()));
+                located(Rc::new(Expression::Pair(lambda, Rc::new(Expression::Pair(params, body)))), form)
+            },
+            Definition::Value(Some(value)) => value,
+            Definition::Value(None) => return Err(bad_syntax("define-macro", form)),
+        };
+
+        let transformer = Transformer::Procedure(self.eval_transformer(&procedure)?);
+        let m = Macro { name: name.identifier_name().unwrap().to_string(), transformer };
+        Ok((name, Rc::new(m)))
+    }
+
+    /// Builds the macro described by a transformer spec, which is evaluated in `spec_scope`
+    /// and whose templates refer to bindings in `definition_scope`.
+    fn make_macro(&mut self, name: &Expr, spec: &Expr, spec_scope: &Rc<Scope>, definition_scope: &Rc<Scope>) -> EvalResult<Rc<Macro>> {
+        let name = name.identifier_name().unwrap().to_string();
+
+        match (self.head_meaning(spec, spec_scope), &**spec) {
+            (Some(Meaning::Keyword(keyword)), Expression::Pair(_, tail)) if keyword == "syntax-rules" => {
+                let rules = SyntaxRules::new(tail, definition_scope).map_err(|err| err.at(spec))?;
+                Ok(Rc::new(Macro { name, transformer: Transformer::SyntaxRules(rules) }))
+            },
+            (Some(Meaning::Keyword(keyword)), Expression::Pair(_, tail)) if keyword == "er-macro-transformer" => {
+                match args(tail, "er-macro-transformer")?.as_slice() {
+                    [procedure] => {
+                        let procedure = self.eval_transformer(procedure)?;
+                        let transformer = Transformer::ExplicitRenaming(procedure, definition_scope.clone());
+                        Ok(Rc::new(Macro { name, transformer }))
+                    },
+                    _ => Err(bad_syntax("er-macro-transformer", spec)),
+                }
+            },
+            _ => Err(bad_syntax("transformer", spec)),
+        }
+    }
+
+    /// Evaluates the expression for a transformer procedure. Local variables have no values
+    /// while expanding, so it is expanded and evaluated in the global environment.
+    fn eval_transformer(&self, expr: &Expr) -> EvalResult<Expr> {
+        let mut expander = Expander { env: self.env, variables: Vec::new() };
+        let expanded = expander.expand_expr(expr, &Scope::root())?;
+        let procedure = eval(&expander.finish(&expanded), self.env).map_err(|err| err.at(expr))?;
+
+        if procedure.is_procedure() {
+            Ok(procedure)
+        } else {
+            Err(bad_syntax("transformer", expr))
+        }
+    }
+
+    fn expand_let_syntax(&mut self, keyword: &str, form: &Expr, tail: &Expr, scope: &Rc<Scope>) -> EvalResult<Expr> {
+        let items = args(tail, keyword)?;
+        let (bindings, body) = match items.split_first() {
+            Some((bindings, body)) if !body.is_empty() => (bindings, body),
+            _ => return Err(bad_syntax(keyword, form)),
+        };
+
+        let inner = Scope::extend(scope);
+        let definition_scope = if keyword == "letrec-syntax" { &inner } else { scope };
+
+        for binding in args(bindings, keyword)? {
+            match binding.list_to_vec().as_deref() {
+                Some([name, spec]) if name.is_identifier() => {
+                    let m = self.make_macro(name, spec, scope, definition_scope)?;
+                    inner.bind(name, Binding::Macro(m));
+                },
+                _ => return Err(bad_syntax(keyword, &binding)),
+            }
+        }
+
+        let mut items = vec![Rc::new(Expression::Nil)];
+        items.extend(self.expand_body(body.to_vec(), &inner)?);
+        Ok(Expression::list(vec![keyword_form("lambda", items)]))
+    }
+
+    fn expand_let_values(&mut self, form: &Expr, tail: &Expr, scope: &Rc<Scope>) -> EvalResult<Expr> {
+        let items = args(tail, "let-values")?;
+        let (bindings, body) = match items.split_first() {
+            Some((bindings, body)) if !body.is_empty() => (bindings, body),
+            _ => return Err(bad_syntax("let-values", form)),
+        };
+
+        let inner = Scope::extend(scope);
+        let mut expanded = Vec::new();
+
+        for binding in args(bindings, "let-values")? {
+            match binding.list_to_vec().as_deref() {
+                Some([formals, init]) => {
+                    let init = self.expand_expr(init, scope)?;
+                    let formals = self.bind_params(formals, &inner)?;
+                    expanded.push(Expression::list(vec![formals, init]));
+                },
+                _ => return Err(bad_syntax("let-values", &binding)),
+            }
+        }
+
+        let mut items = vec![Expression::list(expanded)];
+        items.extend(self.expand_body(body.to_vec(), &inner)?);
+        Ok(keyword_form("let-values", items))
+    }
+
+    fn expand_quasiquote(&mut self, template: &Expr, depth: usize, scope: &Rc<Scope>) -> EvalResult<Expr> {
+        use Expression::*;
+
+        match &**template {
+            Pair(head, tail) => {
+                let keyword = head.identifier_name().filter(|_| head.is_identifier());
+
+                if let (Some(keyword), Some([inner])) = (keyword, tail.list_to_vec().as_deref()) {
+                    let inner = match keyword {
+                        "unquote" | "unquote-splicing" if depth == 1 => Some(self.expand_expr(inner, scope)?),
+                        "unquote" | "unquote-splicing" => Some(self.expand_quasiquote(inner, depth - 1, scope)?),
+                        "quasiquote" => Some(self.expand_quasiquote(inner, depth + 1, scope)?),
+                        _ => None,
+                    };
+
+                    if let Some(inner) = inner {
+                        return Ok(keyword_form(keyword, vec![inner]));
+                    }
+                }
+
+                let head = self.expand_quasiquote(head, depth, scope)?;
+                let tail = self.expand_quasiquote(tail, depth, scope)?;
+                Ok(Rc::new(Pair(head, tail)))
+            },
+            Vector(items) => {
+                let items = items.borrow().iter()
+                    .map(|item| self.expand_quasiquote(item, depth, scope))
+                    .collect::<EvalResult<_>>()?;
+                Ok(Rc::new(Vector(RefCell::new(items))))
+            },
+            _ => Ok(strip_syntax(template)),
+        }
+    }
+
+    /// Gives every local variable its final name in the expanded code.
+    fn finish(&self, expanded: &Expr) -> Expr {
+        if self.variables.is_empty() {
+            return expanded.clone();
+        }
+
+        let names = self.variables.iter()
+            .map(|v| {
+                if v.renamed.get() || CORE_FORMS.contains(&v.name.as_str()) {
+                    format!("{}.{}", v.name, v.id + 1)
+                } else {
+                    v.name.clone()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        map_identifiers(expanded, &|id| match &**id {
+            Expression::Symbol(key) if key.starts_with('\0') => {
+                let name = names[key[1..].parse::<usize>().unwrap()].clone();
+                Some(Rc::new(Expression::Symbol(name)))
+            },
+            _ => None,
+        }).unwrap_or_else(|| expanded.clone())
+    }
+}
+
+fn is_keyword(name: &str) -> bool {
+    KEYWORDS.contains(&name) || derived::KEYWORDS.contains(&name)
+}
+
+/// Marks local variables named `name` that are visible from `scope` and would capture a
+/// reference to `target`, or to the global `name` if there is no target.
+fn mark_shadowing(scope: &Rc<Scope>, name: &str, target: Option<&Rc<Variable>>) {
+    let mut current = Some(scope);
+
+    while let Some(scope) = current {
+        let mut found = false;
+
+        for (_, binding) in scope.bindings.borrow().iter() {
+            if let Binding::Variable(variable) = binding {
+                match target {
+                    Some(target) if Rc::ptr_eq(target, variable) => found = true,
+                    _ if variable.name == name => variable.renamed.set(true),
+                    _ => {},
+                }
+            }
+        }
+
+        if found {
+            return;
+        }
+        current = scope.parent.as_ref();
+    }
+}
+
+/// The placeholder for a local variable until its final name is known.
+fn variable_key(variable: &Variable) -> Expr {
+    Rc::new(Expression::Symbol(format!("\0{}", variable.id)))
+}
+
+fn parse_define(form: &Expr, keyword: &str) -> EvalResult<(Expr, Definition)> {
+    use Expression::*;
+
+    if let Pair(_, tail) = &**form {
+        if let Pair(target, rest) = &**tail {
+            if target.is_identifier() {
+                return match args(rest, keyword)?.as_slice() {
+                    [] => Ok((target.clone(), Definition::Value(None))),
+                    [value] => Ok((target.clone(), Definition::Value(Some(value.clone())))),
+                    _ => Err(bad_syntax(keyword, tail)),
+                };
+            }
+
+            if let Pair(name, params) = &**target {
+                if name.is_identifier() {
+                    return Ok((name.clone(), Definition::Procedure(params.clone(), rest.clone())));
+                }
+            }
+        }
+
+        return Err(bad_syntax(keyword, tail));
+    }
+
+    Err(bad_syntax(keyword, form))
+}
+
+/// Splits `(define-values formals init)`, whose formals are a parameter list.
+fn parse_define_values(form: &Expr) -> EvalResult<(Expr, Expr)> {
+    match args(form, "define-values")?.as_slice() {
+        [_, formals, init] if is_formals(formals) => Ok((formals.clone(), init.clone())),
+        _ => Err(bad_syntax("define-values", form)),
+    }
+}
+
+fn is_formals(formals: &Expr) -> bool {
+    match &**formals {
+        Expression::Nil => true,
+        Expression::Pair(head, tail) => head.is_identifier() && is_formals(tail),
+        _ => formals.is_identifier(),
+    }
+}
+
+/// The `rename` procedure given to an explicit-renaming transformer. It makes aliases that
+/// refer to bindings in the macro's definition scope, the same alias for the same identifier.
+fn renamer(scope: &Rc<Scope>) -> Expr {
+    let scope = scope.clone();
+    let renames = RefCell::new(Vec::<(Expr, Expr)>::new());
+
+    let func = move |args: &[Expr]| {
+        let identifier = &args[0];
+        if !identifier.is_identifier() {
+            let kind = EvalErrorKind::WrongType {
+                procedure: "rename".to_string(),
+                expected: "symbol".to_string(),
+                actual: identifier.clone(),
+            };
+            return Err(kind.into());
+        }
+
+        let mut renames = renames.borrow_mut();
+        if let Some((_, alias)) = renames.iter().find(|(id, _)| same_identifier(id, identifier)) {
+            return Ok(alias.clone());
+        }
+
+        let alias = Rc::new(Expression::Alias(Rc::new(Alias { name: identifier.clone(), scope: scope.clone() })));
+        renames.push((identifier.clone(), alias.clone()));
+        Ok(alias)
+    };
+
+    Rc::new(Expression::Primitive(Rc::new(Primitive { name: "rename", min_args: 1, max_args: Some(1), func: Box::new(func) })))
+}
+
+/// The `compare` procedure given to an explicit-renaming transformer, which tests whether two
+/// identifiers in the macro use refer to the same binding.
+fn comparer(env: &Env, scope: &Rc<Scope>) -> Expr {
+    let (env, scope) = (env.clone(), scope.clone());
+
+    let func = move |args: &[Expr]| {
+        let expander = Expander { env: &env, variables: Vec::new() };
+        let same = args.iter().all(|arg| arg.is_identifier())
+            && expander.same_meaning(&args[0], &scope, &args[1], &scope);
+        Ok(Rc::new(Expression::Boolean(same)))
+    };
+
+    Rc::new(Expression::Primitive(Rc::new(Primitive { name: "compare", min_args: 2, max_args: Some(2), func: Box::new(func) })))
+}
+
+/// Renames the symbols a derived form's rewrite introduced, so that they refer to the core
+/// forms and global bindings regardless of the bindings around the original form. Parts of
+/// the rewrite taken from the original form are left untouched.
+fn close_introduced(expansion: &Expr, form: &Expr) -> Expr {
+    fn collect(expr: &Expr, nodes: &mut HashSet<*const Expression>) {
+        let mut expr = expr;
+
+        while nodes.insert(Rc::as_ptr(expr)) {
+            match &**expr {
+                Expression::Pair(head, tail) => {
+                    collect(head, nodes);
+                    expr = tail;
+                },
+                _ => break,
+            }
+        }
+    }
+
+    fn close(expr: &Expr, nodes: &HashSet<*const Expression>, scope: &Rc<Scope>, aliases: &mut Vec<(String, Expr)>) -> Expr {
+        if nodes.contains(&Rc::as_ptr(expr)) {
+            return expr.clone();
+        }
+
+        match &**expr {
+            Expression::Symbol(name) => {
+                if let Some((_, alias)) = aliases.iter().find(|(n, _)| n == name) {
+                    return alias.clone();
+                }
+
+                let alias = Rc::new(Expression::Alias(Rc::new(Alias { name: expr.clone(), scope: scope.clone() })));
+                aliases.push((name.clone(), alias.clone()));
+                alias
+            },
+            Expression::Pair(head, tail) => {
+                Rc::new(Expression::Pair(close(head, nodes, scope, aliases), close(tail, nodes, scope, aliases)))
+            },
+            _ => expr.clone(),
+        }
+    }
+
+    let mut nodes = HashSet::new();
+    collect(form, &mut nodes);
+
+    close(expansion, &nodes, &Scope::root(), &mut Vec::new())
+}
+
+/// Rebuilds `expr`, replacing the identifiers for which `replace` returns a value, or returns
+/// `None` if nothing was replaced.
+fn map_identifiers(expr: &Expr, replace: &dyn Fn(&Expr) -> Option<Expr>) -> Option<Expr> {
+    use Expression::*;
+
+    match &**expr {
+        Symbol(_) | Alias(_) => replace(expr).map(|e| located(e, expr)),
+        Pair(..) => {
+            let mut items = Vec::new();
+            let mut tail = expr;
+            while let Pair(head, rest) = &**tail {
+                items.push((tail.clone(), head.clone(), map_identifiers(head, replace)));
+                tail = rest;
+            }
+
+            let new_tail = map_identifiers(tail, replace);
+            if new_tail.is_none() && items.iter().all(|(_, _, new)| new.is_none()) {
+                return None;
+            }
+
+            let tail = new_tail.unwrap_or_else(|| tail.clone());
+            Some(items.into_iter().rev().fold(tail, |tail, (pair, head, new)| {
+                located(Rc::new(Pair(new.unwrap_or(head), tail)), &pair)
+            }))
+        },
+        Vector(items) => {
+            let items = items.borrow();
+            let new = items.iter().map(|item| map_identifiers(item, replace)).collect::<Vec<_>>();
+            if new.iter().all(Option::is_none) {
+                return None;
+            }
+
+            let items = new.into_iter().zip(items.iter()).map(|(new, old)| new.unwrap_or_else(|| old.clone())).collect();
+            Some(located(Rc::new(Vector(RefCell::new(items))), expr))
+        },
+        _ => None,
+    }
+}
+
+/// Copies the source location of `source`, if it has one, to an expression built from it.
+fn located(expr: Expr, source: &Expr) -> Expr {
+    if !Rc::ptr_eq(&expr, source) {
+        if let Some(span) = span_of(source) {
+            set_span(&expr, span);
+        }
+    }
+
+    expr
+}
+
+fn keyword_form(keyword: &str, items: Vec<Expr>) -> Expr {
+    Expression::list(std::iter::once(Rc::new(Expression::Symbol(keyword.to_string()))).chain(items).collect())
+}
@@ -0,0 +1,159 @@
+#![cfg(test)]
+
+use test_case::test_case;
+
+use crate::data::Source;
+use crate::eval::expander::expand;
+use crate::eval::{Eval, ExprEvaluator};
+use crate::read::parse_source;
+
+const SWAP: &str = "(define-syntax swap! (syntax-rules () ((_ a b) (let ((tmp a)) (set! a b) (set! b tmp)))))";
+const ER_SWAP: &str = "(define-syntax swap! (er-macro-transformer (lambda (form rename compare) \
+    (let ((a (car (cdr form))) (b (car (cdr (cdr form)))) (tmp (rename 'tmp))) \
+      (list (rename 'let) (list (list tmp a)) (list (rename 'set!) a b) (list (rename 'set!) b tmp))))))";
+const MY_UNLESS: &str = "(define-macro (my-unless c . body) (list 'if c #f (cons 'begin body)))";
+const MY_OR: &str = "(define-syntax my-or (syntax-rules () ((_) #f) ((_ e) e) ((_ e r ...) (let ((t e)) (if t t (my-or r ...))))))";
+
+#[test_case(&[SWAP, "(define x 1) (define y 2) (swap! x y) (list x y)"] => "(2 1)"; "simple macro")]
+#[test_case(&[SWAP, "(define tmp 1) (define y 2) (swap! tmp y) (list tmp y)"] => "(2 1)"; "introduced binding does not capture")]
+#[test_case(&[SWAP, "(let ((tmp 1) (other 2)) (swap! tmp other) (list tmp other))"] => "(2 1)"; "introduced binding does not capture local")]
+#[test_case(&[MY_OR, "(define t 5) (my-or #f t)"] => "5"; "recursive macro")]
+#[test_case(&["(define-syntax my-list (syntax-rules () ((_ x ...) (list x ...))))", "(let ((list vector)) (my-list 1 2))"] => "(1 2)"; "introduced reference is not captured")]
+#[test_case(&["(define-syntax my-if (syntax-rules () ((_ c a b) (cond (c a) (else b)))))", "(let ((if list) (else #f)) (my-if #f 1 2))"] => "2"; "introduced keywords are not captured")]
+#[test_case(&["(let ((=> #f)) (cond (#t => 'ok)))"] => "ok"; "shadowed arrow is not a cond keyword")]
+#[test_case(&["(let ((else #f)) (cond (else 'shadowed) (#t 'ok)))"] => "ok"; "shadowed else is not a cond keyword")]
+#[test_case(&["(let ((=> list)) (case 1 ((1) => 'ok)))"] => "ok"; "shadowed arrow is not a case keyword")]
+#[test_case(&["(guard (e (#t (list 'outer e))) (let ((else #f)) (guard (e (else 'inner)) (raise 'x))))"] => "(outer x)"; "shadowed else is not a guard keyword")]
+#[test_case(&["(let ((x 1)) (let-syntax ((get-x (syntax-rules () ((_) x)))) (let ((x 2)) (get-x))))"] => "1"; "local macro refers to its own scope")]
+#[test_case(&["(letrec-syntax ((ev? (syntax-rules () ((_) #t) ((_ x . r) (od? . r)))) (od? (syntax-rules () ((_) #f) ((_ x . r) (ev? . r))))) (ev? 1 2 3 4))"] => "#t"; "letrec syntax")]
+#[test_case(&["(define (f) (define-syntax twice (syntax-rules () ((_ e) (begin e e)))) (define n 0) (twice (set! n (+ n 1))) n) (f)"] => "2"; "internal define syntax")]
+#[test_case(&["(define-syntax def (syntax-rules () ((_ name value) (define name value))))", "(def x 5) (define (f) (def y 6) (+ x y)) (f)"] => "11"; "macro expanding to definition")]
+#[test_case(&["(define-syntax for (syntax-rules (in) ((_ x in (item ...) body) (list (let ((x item)) body) ...))))", "(for n in (1 2 3) (* n n))"] => "(1 4 9)"; "literal in pattern")]
+#[test_case(&["(define-syntax is-else (syntax-rules (else) ((_ else) #t) ((_ x) #f)))", "(list (is-else else) (is-else other) (let ((else 1)) (is-else else)))"] => "(#t #f #f)"; "literal matches by binding")]
+#[test_case(&["(define-syntax flatten (syntax-rules () ((_ (a ...) ...) '(a ... ...))))", "(flatten (1 2) () (3))"] => "(1 2 3)"; "nested ellipsis")]
+#[test_case(&["(define-syntax pairs (syntax-rules () ((_ (k v ...) ...) '((k . (v ...)) ...))))", "(pairs (a 1 2) (b) (c 3))"] => "((a 1 2) (b) (c 3))"; "ellipsis depth two")]
+#[test_case(&["(define-syntax last-of (syntax-rules () ((_ x ... y) 'y)))", "(last-of 1 2 3)"] => "3"; "pattern after ellipsis")]
+#[test_case(&["(define-syntax tail-of (syntax-rules () ((_ x . rest) 'rest)))", "(tail-of 1 2 3)"] => "(2 3)"; "dotted pattern")]
+#[test_case(&["(define-syntax vec (syntax-rules () ((_ #(x ...)) (list x ...))))", "(vec #(1 2))"] => "(1 2)"; "vector pattern")]
+#[test_case(&["(define-syntax second (syntax-rules () ((_ _ x . _) x)))", "(second 1 2 3)"] => "2"; "underscore pattern")]
+#[test_case(&["(define-syntax my-list (syntax-rules ::: () ((_ x :::) (list x :::))))", "(my-list 1 2 3)"] => "(1 2 3)"; "custom ellipsis")]
+#[test_case(&["(define-syntax ell (syntax-rules () ((_) '(... ...))))", "(ell)"] => "..."; "escaped ellipsis")]
+#[test_case(&["(define-syntax def-lister (syntax-rules () ((_ name) (define-syntax name (syntax-rules () ((_ x (... ...)) (list x (... ...))))))))", "(def-lister lst) (lst 1 2)"] => "(1 2)"; "macro defining macro")]
+#[test_case(&["(define-syntax q (syntax-rules () ((_) '(tmp #(tmp)))))", "(q)"] => "(tmp #(tmp))"; "quoted template symbols")]
+#[test_case(&["(define-syntax five (syntax-rules () ((_) 5)))", "(define (five-times x) (* (five) x)) (five-times 2)"] => "10"; "macro used in procedure")]
+#[test_case(&["(define-syntax while (syntax-rules () ((_ c body ...) (let lp () (when c body ... (lp))))))", "(define i 0) (while (< i 100000) (set! i (+ i 1))) i"] => "100000"; "loop macro runs in constant space")]
+#[test_case(&[MY_UNLESS, "(my-unless #f 1 2)"] => "2"; "define macro")]
+#[test_case(&["(define-macro twice (lambda (e) (list 'begin e e)))", "(define n 0) (twice (set! n (+ n 1))) n"] => "2"; "define macro with procedure")]
+#[test_case(&["(define-macro (with-it v . body) (cons 'let (cons (list (list 'it v)) body)))", "(with-it 5 (* it 2))"] => "10"; "define macro is not hygienic")]
+#[test_case(&["(define (wrap x) (list 'quote x))", "(define-macro (q x) (wrap x))", "(q (a b))"] => "(a b)"; "define macro calls global procedure")]
+#[test_case(&["(define (f) (define-macro (inc! v) (list 'set! v (list '+ v 1))) (define n 1) (inc! n) n) (f)"] => "2"; "internal define macro")]
+#[test_case(&["(define k #f)", "(define-macro (m) (call/cc (lambda (c) (set! k c) 1)))", "(m)", "(list (k 2) 'unreached)"] => "2"; "transformer continuation ends the evaluation that calls it")]
+#[test_case(&[MY_UNLESS, SWAP, "(define x 1) (define y 2) (my-unless #f (swap! x y)) (list x y)"] => "(2 1)"; "define macro expanding to macro use")]
+#[test_case(&[ER_SWAP, "(define tmp 1) (define y 2) (swap! tmp y) (list tmp y)"] => "(2 1)"; "explicit renaming")]
+#[test_case(&[ER_SWAP, "(let ((let 1) (set! 2)) (swap! let set!) (list let set!))"] => "(2 1)"; "renamed keywords are not captured")]
+#[test_case(&["(define-syntax my-list (er-macro-transformer (lambda (f r c) (cons (r 'list) (cdr f)))))", "(let ((list vector)) (my-list 1 2))"] => "(1 2)"; "renamed reference is not captured")]
+#[test_case(&["(define-syntax is-else (er-macro-transformer (lambda (f r c) (c (car (cdr f)) (r 'else)))))", "(list (is-else else) (is-else other) (let ((else 1)) (is-else else)))"] => "(#t #f #f)"; "explicit renaming compare")]
+#[test_case(&["(let ((x 1)) (let-syntax ((get-x (er-macro-transformer (lambda (f r c) (r 'x))))) (let ((x 2)) (get-x))))"] => "1"; "local explicit renaming macro")]
+#[test_case(&[MY_OR, "(expand '(my-or a b))"] => "((lambda (t) (if t t b)) a)"; "expand procedure")]
+#[test_case(&[MY_OR, "(expand-once '(my-or a b c))"] => "(let ((t a)) (if t t (my-or b c)))"; "expand once procedure")]
+#[test_case(&["(expand-once '(when a b))"] => "(if a (begin b) (if #f #f))"; "expand once derived form")]
+#[test_case(&["(expand-once '(f (when a b)))"] => "(f (when a b))"; "expand once procedure call")]
+#[test_case(&["(expand 5)"] => "5"; "expand constant")]
+#[test_case(&["(begin (define-syntax one (syntax-rules () ((_) 1))) (one))"] => "1"; "macro defined earlier in begin")]
+fn evaluate(texts: &[&str]) -> String {
+    let mut evaluator = ExprEvaluator::new();
+    let mut result = None;
+
+    for text in texts {
+        for expr in parse_source(&Source::new("test", text)).unwrap() {
+            match evaluator.eval(expr) {
+                Ok(value) => result = Some(value),
+                Err(e) => panic!("{}", e),
+            }
+        }
+    }
+
+    result.unwrap().to_string()
+}
+
+#[test_case(&[SWAP, "(swap! x)"] => "bad syntax in swap!: (swap! x) (at test:1:1)"; "no matching rule")]
+#[test_case(&[SWAP, "(list swap!)"] => "bad syntax in variable reference: swap! (at test:1:7)"; "macro used as variable")]
+#[test_case(&["(if (define x 1) 2)"] => "bad syntax in expression context: (define x 1) (at test:1:5)"; "definition in expression")]
+#[test_case(&["(define-syntax m 5)"] => "bad syntax in transformer: 5 (at test:1:1)"; "invalid transformer")]
+#[test_case(&["(define-syntax m (syntax-rules () ((_ x ...) (x))))", "(m 1 2)"] => "bad syntax in syntax-rules template: x (at test:1:1)"; "missing ellipsis in template")]
+#[test_case(&["(define-macro m 5)"] => "bad syntax in transformer: 5 (at test:1:1)"; "define macro without procedure")]
+#[test_case(&["(define-macro (m) (car 5))", "(m)"] => "car: expected pair, got 5 (at test:1:19)"; "error in define macro transformer")]
+#[test_case(&["(define-macro (m) (raise 'oops))", "(guard (e (#t 'caught)) (m))"] => "uncaught exception: oops (at test:1:19)"; "transformer raise is not caught by guard")]
+#[test_case(&["(define-macro (m) (with-exception-handler (lambda (e) 0) (lambda () (raise 'oops))))", "(m)"] => "exception handler returned from non-continuable raise: oops (at test:1:1)"; "transformer raise reaches transformer handler")]
+#[test_case(&["(define-syntax m (er-macro-transformer))"] => "bad syntax in er-macro-transformer: (er-macro-transformer) (at test:1:1)"; "explicit renaming without procedure")]
+#[test_case(&["(define (f x) (define-macro (m) x) (m))"] => "unbound variable: x (at test:1:33)"; "transformer cannot see local variables")]
+#[test_case(&["(expand '(define-syntax foo (syntax-rules () ((_) 1))))", "foo"] => "unbound variable: foo (at test:1:1)"; "expand does not define macros")]
+#[test_case(&["(expand '(begin (define-macro (foo) 1) (foo)))", "foo"] => "unbound variable: foo (at test:1:1)"; "expand does not define procedural macros")]
+fn error_message(texts: &[&str]) -> String {
+    let mut evaluator = ExprEvaluator::new();
+
+    for text in texts {
+        for expr in parse_source(&Source::new("test", text)).unwrap() {
+            if let Err(err) = evaluator.eval(expr) {
+                return err.to_string();
+            }
+        }
+    }
+
+    panic!("expected error evaluating {:?}", texts);
+}
+
+#[test_case(&[], "(let ((x 1)) (+ x 1))" => "((lambda (x) (+ x 1)) 1)"; "local names are kept")]
+#[test_case(&[], "`(a ,(let ((b 1)) b) ,@c)" => "`(a ,((lambda (b) b) 1) ,@c)"; "quasiquote")]
+#[test_case(&[SWAP], "(lambda (tmp x) (swap! tmp x))" => "(lambda (tmp x) ((lambda (tmp.3) (set! tmp x) (set! x tmp.3)) tmp))"; "capturing alias is renamed")]
+#[test_case(&["(define-syntax first (syntax-rules () ((_ l) (car l))))"], "(lambda (car) (first car))" => "(lambda (car.1) (car car.1))"; "local shadowing a global is renamed")]
+#[test_case(&[], "(lambda (if) if)" => "(lambda (if.1) if.1)"; "local named like a keyword is renamed")]
+#[test_case(&[MY_OR], "(my-or a b)" => "((lambda (t) (if t t b)) a)"; "nested expansion")]
+#[test_case(&[], "(let loop ((i 0)) (loop i))" => "(((lambda () (define loop (lambda (i) (loop i))) ((lambda () loop)))) 0)"; "named let")]
+#[test_case(&[MY_UNLESS], "(my-unless a b)" => "(if a #f (begin b))"; "define macro expansion")]
+#[test_case(&[ER_SWAP], "(lambda (tmp x) (swap! tmp x))" => "(lambda (tmp x) ((lambda (tmp.3) (set! tmp x) (set! x tmp.3)) tmp))"; "explicit renaming expansion")]
+#[test_case(&[], "(define-syntax one (syntax-rules () ((_) 1)))" => "(define one #<macro one>)"; "macro definition")]
+fn expansion(definitions: &[&str], text: &str) -> String {
+    let mut evaluator = ExprEvaluator::new();
+
+    for definition in definitions {
+        for expr in parse_source(&Source::new("test", definition)).unwrap() {
+            evaluator.eval(expr).unwrap();
+        }
+    }
+
+    let expr = parse_source(&Source::new("test", text)).unwrap().pop().unwrap();
+    expand(&expr, evaluator.env()).unwrap().to_string()
+}
+
+#[test_case(&[MY_OR], ",expand (my-or a b)" => Some("((lambda (t) (if t t b)) a)".to_string()); "expand command")]
+#[test_case(&[MY_OR], ",expand-once (my-or a b c)" => Some("(let ((t a)) (if t t (my-or b c)))".to_string()); "expand once command")]
+#[test_case(&[], ",expand (begin (define-syntax one (syntax-rules () ((_) 1))) (one))" => Some("(begin (define one #<macro one>) 1)".to_string()); "expand command with macro definition")]
+#[test_case(&[], ",other (a b)" => None; "unknown command")]
+#[test_case(&[], "(unquote) (a b)" => None; "not a command")]
+fn repl_command(definitions: &[&str], text: &str) -> Option<String> {
+    let mut evaluator = ExprEvaluator::new();
+
+    for definition in definitions {
+        for expr in parse_source(&Source::new("test", definition)).unwrap() {
+            evaluator.eval(expr).unwrap();
+        }
+    }
+
+    let mut exprs = parse_source(&Source::new("test", text)).unwrap().into_iter();
+    let name = evaluator.command(&exprs.next().unwrap())?;
+    Some(evaluator.run_command(&name, exprs.next().unwrap()).unwrap().to_string())
+}
+
+#[test]
+fn expand_command_defines_nothing() {
+    let mut evaluator = ExprEvaluator::new();
+    let text = ",expand (define-syntax foo (syntax-rules () ((_) 1))) foo";
+    let mut exprs = parse_source(&Source::new("test", text)).unwrap().into_iter();
+
+    let name = evaluator.command(&exprs.next().unwrap()).unwrap();
+    evaluator.run_command(&name, exprs.next().unwrap()).unwrap();
+
+    let err = evaluator.eval(exprs.next().unwrap()).unwrap_err();
+    assert_eq!(err.to_string(), "unbound variable: foo (at test:1:55)");
+}
@@ -1,12 +1,905 @@
-use crate::data::Expr;
-use crate::eval::{Eval, EvalResult};
+use std::cell::RefCell;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::fs;
+use std::rc::Rc;
 
-pub struct ExprEvaluator;
+use crate::data::{Closure, Control, ErrorObject, ErrorObjectKind, Expr, Expression, Source, Values};
+use crate::eval::{expander, primitives, Env, Environment, Eval, EvalError, EvalErrorKind, EvalResult};
+use crate::read::{parse_source, ReadError};
+
+pub struct ExprEvaluator {
+    env: Env,
+}
+
+impl ExprEvaluator {
+    pub fn new() -> Self {
+        let env = Environment::new();
+        primitives::register(&env);
+        expander::register(&env);
+
+        ExprEvaluator { env }
+    }
+
+    pub fn env(&self) -> &Env {
+        &self.env
+    }
+}
+
+impl Default for ExprEvaluator {
+    fn default() -> Self {
+        ExprEvaluator::new()
+    }
+}
 
 impl Eval<Expr> for ExprEvaluator {
-    type Output = Expr;
+    type Output = Values;
+
+    fn eval(&mut self, value: Expr) -> EvalResult<Values> {
+        let expanded = expander::expand(&value, &self.env)?;
+        eval(&expanded, &self.env).map(Values::from).map_err(|err| err.at(&value))
+    }
+
+    /// Recognizes `,expand` and `,expand-once`, which the reader reads as `(unquote expand)`
+    /// and `(unquote expand-once)`.
+    fn command(&self, value: &Expr) -> Option<String> {
+        match value.list_to_vec().as_deref() {
+            Some([head, name]) if head.identifier_name() == Some("unquote") => match name.identifier_name() {
+                Some(name @ "expand") | Some(name @ "expand-once") => Some(name.to_string()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn run_command(&mut self, name: &str, operand: Expr) -> EvalResult<Values> {
+        let expansion = match name {
+            "expand" => expander::expand(&operand, &self.env)?,
+            "expand-once" => expander::expand_once(&operand, &self.env)?,
+            _ => return Err(EvalErrorKind::UnknownCommand(name.to_string()).into()),
+        };
+        Ok(Values::from(expansion))
+    }
+}
+
+/// A captured continuation: a copy of the evaluator's control stack, and the `dynamic-wind`
+/// calls and exception handlers that were active where it was captured.
+pub struct Continuation {
+    stack: Vec<Frame>,
+    winders: Winders,
+    handlers: Handlers,
+}
+
+/// The `dynamic-wind` calls whose thunks are running, innermost first.
+type Winders = Option<Rc<Winder>>;
+
+struct Winder {
+    before: Expr,
+    after: Expr,
+    depth: usize,
+    parent: Winders,
+}
+
+/// The exception handlers installed by `with-exception-handler`, innermost first.
+type Handlers = Option<Rc<Handler>>;
+
+struct Handler {
+    handler: Expr,
+    parent: Handlers,
+}
+
+/// What remains to be done with the value of the expression being evaluated. Frames are kept
+/// on the evaluator's own stack rather than the native one, so that continuations can copy it.
+#[derive(Clone)]
+enum Frame {
+    /// Chooses a branch of an `if` by the value of its test.
+    Test { expr: Expr, consequent: Expr, alternative: Option<Expr>, env: Env },
+    Define { expr: Expr, name: String, env: Env },
+    DefineValues { expr: Expr, formals: Expr, env: Env },
+    Set { expr: Expr, name: String, env: Env },
+    /// Evaluates the rest of a body, starting with the form at `next`.
+    Body { forms: Rc<[Expr]>, next: usize, env: Env },
+    /// Evaluates the operator and operands of a call in order, then applies the operator.
+    /// `rest` is the list of operands not yet evaluated.
+    Call { expr: Expr, rest: Expr, values: Vec<Expr>, env: Env },
+    /// Evaluates the unquoted parts of a quasiquote template in order, then fills them in.
+    Quasiquote { expr: Expr, template: Expr, parts: Rc<[Expr]>, values: Vec<Expr>, env: Env },
+    /// Binds the formals of each `let-values` binding in turn, then evaluates the body.
+    LetValues { expr: Expr, bindings: Rc<[(Expr, Expr)]>, next: usize, body: Rc<[Expr]>, body_env: Env, env: Env },
+    /// Enters a `dynamic-wind` once its before thunk has returned.
+    WindBefore { before: Expr, thunk: Expr, after: Expr },
+    /// Leaves a `dynamic-wind` once its thunk has returned, by calling the after thunk.
+    WindAfter { winder: Rc<Winder> },
+    /// Calls a before or after thunk on the way to a continuation.
+    Rewind { thunk: Expr, winders: Winders },
+    /// Returns a value in place of the one it receives.
+    Resume { value: Expr, winders: Winders },
+    /// Reinstates the exception handlers once a handler or the thunk of a
+    /// `with-exception-handler` returns.
+    RestoreHandlers { handlers: Handlers },
+    /// Fails once the handler for a non-continuable `raise` returns.
+    Raised { obj: Expr },
+    /// Applies the consumer of a `call-with-values` to the values its producer returns.
+    Receive { consumer: Expr },
+    /// Expands and evaluates the rest of a loaded file, starting with the form at `next`.
+    Load { forms: Rc<[Expr]>, next: usize },
+}
+
+enum State {
+    Eval(Expr, Env),
+    Return(Expr),
+}
+
+/// Evaluates expressions one step at a time, keeping the rest of the computation as a stack of
+/// frames. Tail positions push no frames, so tail calls run in constant space.
+struct Machine {
+    stack: Vec<Frame>,
+    winders: Winders,
+    handlers: Handlers,
+    /// The environment `load` evaluates files in.
+    env: Env,
+}
+
+pub (crate) fn eval(expr: &Expr, env: &Env) -> EvalResult<Expr> {
+    Machine::new(env).run(State::Eval(expr.clone(), env.clone()))
+}
+
+/// Applies a procedure to its operands and evaluates the call to completion, with `env` as the
+/// environment `load` evaluates files in. The expander calls macro transformers this way, so
+/// they run outside the dynamic context of any evaluation: an exception a transformer does not
+/// handle itself becomes an error of the expansion, and a continuation it captures holds only
+/// the rest of the call, ending whatever evaluation later calls it with the value it is given.
+pub (crate) fn call(operator: &Expr, operands: Vec<Expr>, env: &Env) -> EvalResult<Expr> {
+    let mut machine = Machine::new(env);
+    let state = machine.apply(operator, operands)?;
+    machine.run(state)
+}
+
+impl Machine {
+    fn new(env: &Env) -> Self {
+        Machine { stack: Vec::new(), winders: None, handlers: None, env: env.clone() }
+    }
+
+    fn run(&mut self, mut state: State) -> EvalResult<Expr> {
+        loop {
+            let next = match state {
+                State::Eval(expr, env) => self.eval_step(&expr, &env).map_err(|err| self.locate(err.at(&expr))),
+                State::Return(value) => match self.stack.pop() {
+                    Some(frame) => {
+                        let expr = frame.expr().cloned();
+                        self.resume(frame, value).map_err(|err| match &expr {
+                            Some(expr) => self.locate(err.at(expr)),
+                            None => self.locate(err),
+                        })
+                    },
+                    None => return Ok(value),
+                },
+            };
+
+            state = match next {
+                Ok(state) => state,
+                Err(err) => self.signal(err)?,
+            };
+        }
+    }
+
+    /// Raises an error to the current exception handler: the object passed to `raise`, or an
+    /// error object made from any other error. Without a handler, the error is returned.
+    fn signal(&mut self, err: EvalError) -> EvalResult<State> {
+        if self.handlers.is_none() {
+            return Err(err);
+        }
+
+        let obj = match &err.kind {
+            EvalErrorKind::Raise(obj) => obj.clone(),
+            _ => Rc::new(Expression::ErrorObject(Rc::new(ErrorObject::from(err)))),
+        };
+        self.raise(obj, false)
+    }
+
+    /// Calls the current exception handler with `obj`, with the outer handlers installed. If
+    /// the raise is continuable, the handler's value is returned to the raise.
+    fn raise(&mut self, obj: Expr, continuable: bool) -> EvalResult<State> {
+        let handler = match &self.handlers {
+            Some(handler) => handler.clone(),
+            None => return Err(uncaught(obj)),
+        };
+
+        if continuable {
+            self.stack.push(Frame::RestoreHandlers { handlers: self.handlers.clone() });
+        } else {
+            self.stack.push(Frame::Raised { obj: obj.clone() });
+        }
+
+        self.handlers = handler.parent.clone();
+        self.apply(&handler.handler, vec![obj])
+    }
+
+    /// Records the innermost pending expression on an error that has no location yet.
+    fn locate(&self, err: EvalError) -> EvalError {
+        self.stack.iter().rev().filter_map(Frame::expr).fold(err, |err, expr| err.at(expr))
+    }
+
+    fn eval_step(&mut self, expr: &Expr, env: &Env) -> EvalResult<State> {
+        use Expression::*;
+
+        match &**expr {
+            Symbol(name) => env.lookup(name)
+                .map(State::Return)
+                .ok_or_else(|| EvalErrorKind::UnboundVariable(name.clone()).into()),
+            Pair(head, tail) => {
+                if let Symbol(name) = &**head {
+                    match name.as_str() {
+                        "quote" => return eval_quote(tail).map(State::Return),
+                        "quasiquote" => return self.eval_quasiquote(expr, tail, env),
+                        "if" => return self.eval_if(expr, tail, env),
+                        "define" => return self.eval_define(expr, tail, env),
+                        "define-values" => return self.eval_define_values(expr, tail, env),
+                        "set!" => return self.eval_set(expr, tail, env),
+                        "lambda" => return eval_lambda(tail, env).map(State::Return),
+                        "begin" => return Ok(self.eval_body(args(tail, "begin")?.into(), env)),
+                        "let-values" => return self.eval_let_values(expr, tail, env),
+                        _ => {},
+                    }
+                }
+
+                if !tail.is_list() {
+                    return Err(bad_syntax("procedure call", tail));
+                }
+
+                self.stack.push(Frame::Call { expr: expr.clone(), rest: tail.clone(), values: Vec::new(), env: env.clone() });
+                Ok(State::Eval(head.clone(), env.clone()))
+            },
+            Nil => Err(bad_syntax("procedure call", expr)),
+            _ => Ok(State::Return(expr.clone())),
+        }
+    }
+
+    /// Continues the computation a frame describes with the value it was waiting for.
+    fn resume(&mut self, frame: Frame, value: Expr) -> EvalResult<State> {
+        match frame {
+            Frame::Test { consequent, alternative, env, .. } => match (value.is_true(), alternative) {
+                (true, _) => Ok(State::Eval(consequent, env)),
+                (false, Some(alternative)) => Ok(State::Eval(alternative, env)),
+                (false, None) => Ok(State::Return(Rc::new(Expression::Unspecified))),
+            },
+            Frame::Define { name, env, .. } => {
+                env.define(&name, value);
+                Ok(State::Return(Rc::new(Expression::Unspecified)))
+            },
+            Frame::DefineValues { formals, env, .. } => {
+                bind_values("define-values", &env, &formals, value)?;
+                Ok(State::Return(Rc::new(Expression::Unspecified)))
+            },
+            Frame::Set { name, env, .. } => {
+                if env.set(&name, value) {
+                    Ok(State::Return(Rc::new(Expression::Unspecified)))
+                } else {
+                    Err(EvalErrorKind::UnboundVariable(name).into())
+                }
+            },
+            Frame::Body { forms, next, env } => {
+                let form = forms[next].clone();
+                if next + 1 < forms.len() {
+                    self.stack.push(Frame::Body { forms, next: next + 1, env: env.clone() });
+                }
+
+                Ok(State::Eval(form, env))
+            },
+            Frame::Call { expr, rest, mut values, env } => {
+                values.push(value);
+                if let Expression::Pair(operand, rest) = &*rest {
+                    let operand = operand.clone();
+                    self.stack.push(Frame::Call { expr, rest: rest.clone(), values, env: env.clone() });
+                    return Ok(State::Eval(operand, env));
+                }
+
+                let operator = values.remove(0);
+                self.apply(&operator, values)
+            },
+            Frame::Quasiquote { expr, template, parts, mut values, env } => {
+                values.push(value);
+                if values.len() < parts.len() {
+                    let part = parts[values.len()].clone();
+                    self.stack.push(Frame::Quasiquote { expr, template, parts, values, env: env.clone() });
+                    return Ok(State::Eval(part, env));
+                }
+
+                fill(&template, 1, &mut values.into_iter()).map(State::Return)
+            },
+            Frame::LetValues { expr, bindings, next, body, body_env, env } => {
+                bind_values("let-values", &body_env, &bindings[next].0, value)?;
+
+                if next + 1 < bindings.len() {
+                    let init = bindings[next + 1].1.clone();
+                    self.stack.push(Frame::LetValues { expr, bindings, next: next + 1, body, body_env, env: env.clone() });
+                    return Ok(State::Eval(init, env));
+                }
+
+                Ok(self.eval_body(body, &body_env))
+            },
+            Frame::WindBefore { before, thunk, after } => {
+                let depth = self.winders.as_ref().map_or(1, |winder| winder.depth + 1);
+                let winder = Rc::new(Winder { before, after, depth, parent: self.winders.clone() });
+
+                self.winders = Some(winder.clone());
+                self.stack.push(Frame::WindAfter { winder });
+                self.apply(&thunk, vec![])
+            },
+            Frame::WindAfter { winder } => {
+                self.winders = winder.parent.clone();
+                self.stack.push(Frame::Resume { value, winders: self.winders.clone() });
+                self.apply(&winder.after, vec![])
+            },
+            Frame::Rewind { thunk, winders } => {
+                self.winders = winders;
+                self.apply(&thunk, vec![])
+            },
+            Frame::Resume { value, winders } => {
+                self.winders = winders;
+                Ok(State::Return(value))
+            },
+            Frame::RestoreHandlers { handlers } => {
+                self.handlers = handlers;
+                Ok(State::Return(value))
+            },
+            Frame::Raised { obj } => Err(EvalErrorKind::NonContinuable(obj).into()),
+            Frame::Receive { consumer } => self.apply(&consumer, Values::from(value).into_vec()),
+            Frame::Load { forms, next } => self.load(forms, next),
+        }
+    }
+
+    /// Expands and evaluates the forms of a loaded file in order, each once the ones before it
+    /// have run, so that they can use the macros those define.
+    fn load(&mut self, forms: Rc<[Expr]>, next: usize) -> EvalResult<State> {
+        let form = match forms.get(next) {
+            Some(form) => expander::expand(form, &self.env).map_err(|err| err.at(form))?,
+            None => return Ok(State::Return(Rc::new(Expression::Unspecified))),
+        };
+
+        if next + 1 < forms.len() {
+            self.stack.push(Frame::Load { forms, next: next + 1 });
+        }
+
+        Ok(State::Eval(form, self.env.clone()))
+    }
+
+    /// Evaluates the forms of a body in order, leaving the last in tail position.
+    fn eval_body(&mut self, forms: Rc<[Expr]>, env: &Env) -> State {
+        match forms.first().cloned() {
+            Some(first) => {
+                if forms.len() > 1 {
+                    self.stack.push(Frame::Body { forms, next: 1, env: env.clone() });
+                }
+
+                State::Eval(first, env.clone())
+            },
+            None => State::Return(Rc::new(Expression::Unspecified)),
+        }
+    }
+
+    fn eval_quasiquote(&mut self, expr: &Expr, tail: &Expr, env: &Env) -> EvalResult<State> {
+        let template = match args(tail, "quasiquote")?.as_slice() {
+            [template] => template.clone(),
+            _ => return Err(bad_syntax("quasiquote", tail)),
+        };
+
+        let mut parts = Vec::new();
+        unquoted(&template, 1, &mut parts);
+
+        match parts.first().cloned() {
+            Some(first) => {
+                let values = Vec::with_capacity(parts.len());
+                self.stack.push(Frame::Quasiquote { expr: expr.clone(), template, parts: parts.into(), values, env: env.clone() });
+                Ok(State::Eval(first, env.clone()))
+            },
+            None => fill(&template, 1, &mut std::iter::empty()).map(State::Return),
+        }
+    }
+
+    fn eval_if(&mut self, expr: &Expr, tail: &Expr, env: &Env) -> EvalResult<State> {
+        let (test, consequent, alternative) = match args(tail, "if")?.as_slice() {
+            [test, consequent] => (test.clone(), consequent.clone(), None),
+            [test, consequent, alternative] => (test.clone(), consequent.clone(), Some(alternative.clone())),
+            _ => return Err(bad_syntax("if", tail)),
+        };
+
+        self.stack.push(Frame::Test { expr: expr.clone(), consequent, alternative, env: env.clone() });
+        Ok(State::Eval(test, env.clone()))
+    }
+
+    fn eval_define(&mut self, expr: &Expr, tail: &Expr, env: &Env) -> EvalResult<State> {
+        use Expression::*;
+
+        let unspecified = || Ok(State::Return(Rc::new(Unspecified)));
+
+        match &**tail {
+            Pair(target, rest) => match &**target {
+                Symbol(name) => match args(rest, "define")?.as_slice() {
+                    [] => {
+                        env.define(name, Rc::new(Unspecified));
+                        unspecified()
+                    },
+                    [value] => match lambda_form(value) {
+                        Some((params, body)) => {
+                            env.define(name, make_closure(Some(name), params, body, env)?);
+                            unspecified()
+                        },
+                        None => {
+                            self.stack.push(Frame::Define { expr: expr.clone(), name: name.clone(), env: env.clone() });
+                            Ok(State::Eval(value.clone(), env.clone()))
+                        },
+                    },
+                    _ => Err(bad_syntax("define", tail)),
+                },
+                Pair(name, params) => match &**name {
+                    Symbol(name) => {
+                        env.define(name, make_closure(Some(name), params, rest, env)?);
+                        unspecified()
+                    },
+                    _ => Err(bad_syntax("define", tail)),
+                },
+                _ => Err(bad_syntax("define", tail)),
+            },
+            _ => Err(bad_syntax("define", tail)),
+        }
+    }
+
+    fn eval_define_values(&mut self, expr: &Expr, tail: &Expr, env: &Env) -> EvalResult<State> {
+        match args(tail, "define-values")?.as_slice() {
+            [formals, init] => {
+                self.stack.push(Frame::DefineValues { expr: expr.clone(), formals: formals.clone(), env: env.clone() });
+                Ok(State::Eval(init.clone(), env.clone()))
+            },
+            _ => Err(bad_syntax("define-values", tail)),
+        }
+    }
+
+    fn eval_set(&mut self, expr: &Expr, tail: &Expr, env: &Env) -> EvalResult<State> {
+        match args(tail, "set!")?.as_slice() {
+            [target, value] => match &**target {
+                Expression::Symbol(name) => {
+                    self.stack.push(Frame::Set { expr: expr.clone(), name: name.clone(), env: env.clone() });
+                    Ok(State::Eval(value.clone(), env.clone()))
+                },
+                _ => Err(bad_syntax("set!", tail)),
+            },
+            _ => Err(bad_syntax("set!", tail)),
+        }
+    }
+
+    fn eval_let_values(&mut self, form: &Expr, tail: &Expr, env: &Env) -> EvalResult<State> {
+        let items = args(tail, "let-values")?;
+        let (bindings, body) = match items.split_first() {
+            Some((bindings, body)) if !body.is_empty() => (bindings, body),
+            _ => return Err(bad_syntax("let-values", form)),
+        };
+
+        let bindings = args(bindings, "let-values")?.iter()
+            .map(|binding| match binding.list_to_vec().as_deref() {
+                Some([formals, init]) => Ok((formals.clone(), init.clone())),
+                _ => Err(bad_syntax("let-values", binding)),
+            })
+            .collect::<EvalResult<Rc<[_]>>>()?;
+
+        let body_env = Environment::extend(env);
+        let body = body.iter().cloned().collect::<Rc<[_]>>();
+
+        match bindings.first() {
+            Some((_, init)) => {
+                let init = init.clone();
+                self.stack.push(Frame::LetValues { expr: form.clone(), bindings, next: 0, body, body_env, env: env.clone() });
+                Ok(State::Eval(init, env.clone()))
+            },
+            None => Ok(self.eval_body(body, &body_env)),
+        }
+    }
+
+    fn apply(&mut self, operator: &Expr, operands: Vec<Expr>) -> EvalResult<State> {
+        match &**operator {
+            Expression::Primitive(primitive) => {
+                if !primitive.accepts(operands.len()) {
+                    return Err(arity_error(operator, primitive.min_args, primitive.max_args, operands.len()));
+                }
+
+                (primitive.func)(&operands).map(State::Return)
+            },
+            Expression::Closure(closure) => {
+                let Closure { params, rest, body, env, .. } = &**closure;
+
+                if !closure.accepts(operands.len()) {
+                    let max_args = if rest.is_some() { None } else { Some(params.len()) };
+                    return Err(arity_error(operator, params.len(), max_args, operands.len()));
+                }
+
+                let env = Environment::extend(env);
+                bind(&env, params, rest, operands);
+
+                Ok(self.eval_body(body.clone(), &env))
+            },
+            Expression::Control(control) => {
+                let (min_args, max_args) = control.arity();
+                if operands.len() < min_args || max_args.is_some_and(|max| operands.len() > max) {
+                    return Err(arity_error(operator, min_args, max_args, operands.len()));
+                }
 
-    fn eval(&mut self, value: Expr) -> EvalResult<Expr> {
-        Ok(value)
+                self.apply_control(*control, operands)
+            },
+            Expression::Continuation(k) => Ok(self.reenter(k, Values::new(operands).into_expr())),
+            _ => Err(EvalErrorKind::NotProcedure(operator.clone()).into()),
+        }
     }
+
+    fn apply_control(&mut self, control: Control, operands: Vec<Expr>) -> EvalResult<State> {
+        match control {
+            Control::CallWithCurrentContinuation => {
+                let k = Continuation {
+                    stack: self.stack.clone(),
+                    winders: self.winders.clone(),
+                    handlers: self.handlers.clone(),
+                };
+                self.apply(&operands[0], vec![Rc::new(Expression::Continuation(Rc::new(k)))])
+            },
+            Control::DynamicWind => {
+                let (before, thunk, after) = (operands[0].clone(), operands[1].clone(), operands[2].clone());
+                self.stack.push(Frame::WindBefore { before: before.clone(), thunk, after });
+                self.apply(&before, vec![])
+            },
+            Control::WithExceptionHandler => {
+                let (handler, thunk) = (operands[0].clone(), operands[1].clone());
+                if !handler.is_procedure() {
+                    let kind = EvalErrorKind::WrongType {
+                        procedure: control.name().to_string(),
+                        expected: "procedure".to_string(),
+                        actual: handler,
+                    };
+                    return Err(kind.into());
+                }
+
+                self.stack.push(Frame::RestoreHandlers { handlers: self.handlers.clone() });
+                self.handlers = Some(Rc::new(Handler { handler, parent: self.handlers.clone() }));
+                self.apply(&thunk, vec![])
+            },
+            Control::RaiseContinuable => self.raise(operands[0].clone(), true),
+            Control::CallWithValues => {
+                let (producer, consumer) = (operands[0].clone(), operands[1].clone());
+                self.stack.push(Frame::Receive { consumer });
+                self.apply(&producer, vec![])
+            },
+            Control::Load => self.load(read_file(&operands[0])?.into(), 0),
+        }
+    }
+
+    /// Replaces the control stack with a continuation's, running the after thunks of the
+    /// `dynamic-wind` calls being left and the before thunks of those being entered on the way.
+    fn reenter(&mut self, k: &Continuation, value: Expr) -> State {
+        fn depth(winders: &Winders) -> usize {
+            winders.as_ref().map_or(0, |winder| winder.depth)
+        }
+
+        fn pop(winders: &mut Winders, popped: &mut Vec<Rc<Winder>>) {
+            let winder = winders.take().unwrap();
+            *winders = winder.parent.clone();
+            popped.push(winder);
+        }
+
+        let (mut from, mut afters) = (self.winders.clone(), Vec::new());
+        let (mut to, mut befores) = (k.winders.clone(), Vec::new());
+
+        while depth(&from) > depth(&to) {
+            pop(&mut from, &mut afters);
+        }
+        while depth(&to) > depth(&from) {
+            pop(&mut to, &mut befores);
+        }
+        while !same_winders(&from, &to) {
+            pop(&mut from, &mut afters);
+            pop(&mut to, &mut befores);
+        }
+
+        // Frames run from the top of the stack: after thunks innermost first, then before
+        // thunks outermost first, then the value is returned to the continuation.
+        self.stack = k.stack.clone();
+        self.handlers = k.handlers.clone();
+        self.stack.push(Frame::Resume { value, winders: k.winders.clone() });
+        for winder in befores {
+            self.stack.push(Frame::Rewind { thunk: winder.before.clone(), winders: winder.parent.clone() });
+        }
+        for winder in afters.into_iter().rev() {
+            self.stack.push(Frame::Rewind { thunk: winder.after.clone(), winders: winder.parent.clone() });
+        }
+
+        State::Return(Rc::new(Expression::Unspecified))
+    }
+}
+
+impl Frame {
+    /// The expression whose evaluation the frame is part of, for locating errors.
+    fn expr(&self) -> Option<&Expr> {
+        match self {
+            Frame::Test { expr, .. }
+            | Frame::Define { expr, .. }
+            | Frame::DefineValues { expr, .. }
+            | Frame::Set { expr, .. }
+            | Frame::Call { expr, .. }
+            | Frame::Quasiquote { expr, .. }
+            | Frame::LetValues { expr, .. } => Some(expr),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for Continuation {
+    fn eq(&self, other: &Continuation) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Debug for Continuation {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Display::fmt(self, f)
+    }
+}
+
+impl Display for Continuation {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "#<continuation>")
+    }
+}
+
+/// The error for an object raised with no handler installed. An error object made from an
+/// evaluator error is reported as that error.
+fn uncaught(obj: Expr) -> EvalError {
+    if let Expression::ErrorObject(error) = &*obj {
+        if let Some(err) = &error.error {
+            return err.clone();
+        }
+    }
+
+    EvalErrorKind::Raise(obj).into()
+}
+
+/// Reads the forms in a file for `load`. A file that cannot be opened raises a file error, and
+/// one that cannot be parsed a read error.
+fn read_file(path: &Expr) -> EvalResult<Vec<Expr>> {
+    fn raise(kind: ErrorObjectKind, message: String, irritants: Vec<Expr>) -> EvalError {
+        let error = ErrorObject::new(message, irritants).with_kind(kind);
+        EvalErrorKind::Raise(Rc::new(Expression::ErrorObject(Rc::new(error)))).into()
+    }
+
+    let name = match &**path {
+        Expression::String(s) => s.borrow().clone(),
+        _ => {
+            let kind = EvalErrorKind::WrongType {
+                procedure: Control::Load.name().to_string(),
+                expected: "string".to_string(),
+                actual: path.clone(),
+            };
+            return Err(kind.into());
+        },
+    };
+
+    let text = fs::read_to_string(&name)
+        .map_err(|err| raise(ErrorObjectKind::File, format!("load: {}", err), vec![path.clone()]))?;
+
+    parse_source(&Source::new(&name, text)).map_err(|err| {
+        let message = match err {
+            ReadError::Parse(diagnostic) => {
+                format!("load: {} in {}:{}:{}", diagnostic.message, name, diagnostic.line(), diagnostic.column())
+            },
+            _ => format!("load: {}", err),
+        };
+        raise(ErrorObjectKind::Read, message, vec![])
+    })
+}
+
+fn same_winders(a: &Winders, b: &Winders) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn eval_quote(tail: &Expr) -> EvalResult<Expr> {
+    match args(tail, "quote")?.as_slice() {
+        [datum] => Ok(datum.clone()),
+        _ => Err(bad_syntax("quote", tail)),
+    }
+}
+
+/// Collects the expressions unquoted at depth one in a quasiquote template, in the order that
+/// `fill` takes their values.
+fn unquoted(template: &Expr, depth: usize, parts: &mut Vec<Expr>) {
+    use Expression::*;
+
+    if let Some(inner) = form_argument(template, "unquote") {
+        if depth == 1 {
+            parts.push(inner.clone());
+        } else {
+            unquoted(inner, depth - 1, parts);
+        }
+        return;
+    }
+
+    if let Some(inner) = form_argument(template, "quasiquote") {
+        return unquoted(inner, depth + 1, parts);
+    }
+
+    match &**template {
+        Pair(head, tail) => {
+            match form_argument(head, "unquote-splicing") {
+                Some(inner) if depth == 1 => parts.push(inner.clone()),
+                Some(inner) => unquoted(inner, depth - 1, parts),
+                None => unquoted(head, depth, parts),
+            }
+            unquoted(tail, depth, parts);
+        },
+        Vector(items) => unquoted(&Expression::list(items.borrow().clone()), depth, parts),
+        _ => {},
+    }
+}
+
+/// Builds the value of a quasiquote template from the values of its unquoted expressions.
+fn fill(template: &Expr, depth: usize, values: &mut dyn Iterator<Item = Expr>) -> EvalResult<Expr> {
+    use Expression::*;
+
+    fn tagged(keyword: &str, value: Expr) -> Expr {
+        Expression::list(vec![Rc::new(Expression::Symbol(keyword.to_string())), value])
+    }
+
+    if let Some(inner) = form_argument(template, "unquote") {
+        return if depth == 1 {
+            Ok(values.next().unwrap())
+        } else {
+            Ok(tagged("unquote", fill(inner, depth - 1, values)?))
+        };
+    }
+
+    if let Some(inner) = form_argument(template, "quasiquote") {
+        return Ok(tagged("quasiquote", fill(inner, depth + 1, values)?));
+    }
+
+    match &**template {
+        Pair(head, tail) => match form_argument(head, "unquote-splicing") {
+            Some(_) if depth == 1 => {
+                let spliced = values.next().unwrap();
+                let items = spliced.list_to_vec()
+                    .ok_or_else(|| EvalError::from(EvalErrorKind::WrongType {
+                        procedure: "unquote-splicing".to_string(),
+                        expected: "list".to_string(),
+                        actual: spliced.clone(),
+                    }))?;
+
+                let tail = fill(tail, depth, values)?;
+                Ok(items.into_iter().rev().fold(tail, |tail, head| Rc::new(Pair(head, tail))))
+            },
+            Some(inner) => {
+                let head = tagged("unquote-splicing", fill(inner, depth - 1, values)?);
+                Ok(Rc::new(Pair(head, fill(tail, depth, values)?)))
+            },
+            None => {
+                let head = fill(head, depth, values)?;
+                Ok(Rc::new(Pair(head, fill(tail, depth, values)?)))
+            },
+        },
+        Vector(items) => {
+            let items = fill(&Expression::list(items.borrow().clone()), depth, values)?;
+            Ok(Rc::new(Vector(RefCell::new(items.list_to_vec().unwrap()))))
+        },
+        _ => Ok(template.clone()),
+    }
+}
+
+fn eval_lambda(tail: &Expr, env: &Env) -> EvalResult<Expr> {
+    match &**tail {
+        Expression::Pair(params, body) => make_closure(None, params, body, env),
+        _ => Err(bad_syntax("lambda", tail)),
+    }
+}
+
+fn form_argument<'a>(expr: &'a Expr, keyword: &str) -> Option<&'a Expr> {
+    use Expression::*;
+
+    match &**expr {
+        Pair(head, tail) => match (&**head, &**tail) {
+            (Symbol(name), Pair(arg, rest)) if name == keyword && **rest == Nil => Some(arg),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn lambda_form(expr: &Expr) -> Option<(&Expr, &Expr)> {
+    use Expression::*;
+
+    match &**expr {
+        Pair(head, tail) => match (&**head, &**tail) {
+            (Symbol(name), Pair(params, body)) if name == "lambda" => Some((params, body)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn make_closure(name: Option<&String>, params: &Expr, body: &Expr, env: &Env) -> EvalResult<Expr> {
+    let (names, rest) = parse_params(params)?;
+
+    let body_forms = args(body, "lambda")?;
+    if body_forms.is_empty() {
+        return Err(bad_syntax("lambda", body));
+    }
+
+    Ok(Rc::new(Expression::Closure(Rc::new(Closure {
+        name: name.cloned(),
+        params: names,
+        rest,
+        body: body_forms.into(),
+        env: env.clone(),
+    }))))
+}
+
+/// Splits a parameter list such as `(a b . c)` into its required names and rest name.
+fn parse_params(params: &Expr) -> EvalResult<(Vec<String>, Option<String>)> {
+    let mut names = Vec::new();
+    let mut rest = None;
+    let mut tail = params;
+
+    loop {
+        match &**tail {
+            Expression::Nil => break,
+            Expression::Symbol(name) => {
+                rest = Some(name.clone());
+                break;
+            },
+            Expression::Pair(head, t) => match &**head {
+                Expression::Symbol(name) => {
+                    names.push(name.clone());
+                    tail = t;
+                },
+                _ => return Err(bad_syntax("parameter list", params)),
+            },
+            _ => return Err(bad_syntax("parameter list", params)),
+        }
+    }
+
+    Ok((names, rest))
+}
+
+/// Binds each name to its value and the rest name, if any, to a list of the remaining values.
+fn bind(env: &Env, names: &[String], rest: &Option<String>, values: Vec<Expr>) {
+    let mut values = values.into_iter();
+
+    for name in names {
+        env.define(name, values.next().unwrap());
+    }
+    if let Some(name) = rest {
+        env.define(name, Expression::list(values.collect()));
+    }
+}
+
+/// Binds the formals of a `let-values` or `define-values` form to the values `value` stands for.
+fn bind_values(form: &str, env: &Env, formals: &Expr, value: Expr) -> EvalResult<()> {
+    let (names, rest) = parse_params(formals)?;
+    let values = Values::from(value).into_vec();
+
+    let accepts = if rest.is_some() { values.len() >= names.len() } else { values.len() == names.len() };
+    if !accepts {
+        let max_values = if rest.is_some() { None } else { Some(names.len()) };
+        return Err(arity_error(&Rc::new(Expression::Symbol(form.to_string())), names.len(), max_values, values.len()));
+    }
+
+    bind(env, &names, &rest, values);
+    Ok(())
+}
+
+fn arity_error(operator: &Expr, min_args: usize, max_args: Option<usize>, count: usize) -> EvalError {
+    EvalErrorKind::Arity { procedure: operator.clone(), min_args, max_args, actual: count }.into()
+}
+
+pub (crate) fn args(tail: &Expr, context: &str) -> EvalResult<Vec<Expr>> {
+    tail.list_to_vec().ok_or_else(|| bad_syntax(context, tail))
+}
+
+pub (crate) fn bad_syntax(context: &str, form: &Expr) -> EvalError {
+    EvalErrorKind::BadSyntax { context: context.to_string(), form: form.clone() }.into()
 }
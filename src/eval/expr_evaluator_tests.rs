@@ -0,0 +1,411 @@
+#![cfg(test)]
+
+use test_case::test_case;
+
+use crate::data::Source;
+use crate::eval::{Eval, ExprEvaluator};
+use crate::read::parse_source;
+
+#[test_case("42" => "42"; "number self-evaluates")]
+#[test_case("(quote x)" => "x"; "quoted symbol")]
+#[test_case("(quote (a b . c))" => "(a b . c)"; "quoted list")]
+#[test_case("'(a b)" => "(a b)"; "quote shorthand")]
+#[test_case("''a" => "'a"; "nested quote shorthand")]
+#[test_case("(define b 2) `(a ,b)" => "(a 2)"; "quasiquote with unquote")]
+#[test_case("(define c '(1 2)) `(a ,@c d)" => "(a 1 2 d)"; "quasiquote with splicing")]
+#[test_case("(define c '(1 2)) `(a . ,c)" => "(a 1 2)"; "quasiquote with dotted unquote")]
+#[test_case("`(a `(b ,(c ,(car '(1)))))" => "(a `(b ,(c 1)))"; "nested quasiquote")]
+#[test_case("(define x 5) x" => "5"; "defined variable")]
+#[test_case("(define x 5) (set! x 6) x" => "6"; "assigned variable")]
+#[test_case("#t" => "#t"; "boolean self-evaluates")]
+#[test_case("#\\a" => "#\\a"; "character self-evaluates")]
+#[test_case("(if #f (quote yes) (quote no))" => "no"; "false is false")]
+#[test_case("(if 0 (quote yes) (quote no))" => "yes"; "zero is true")]
+#[test_case("(if (quote ()) (quote yes))" => "yes"; "empty list is true")]
+#[test_case("(begin 1 2 3)" => "3"; "begin returns last value")]
+#[test_case("(begin)" => "#<unspecified>"; "empty begin")]
+#[test_case("((lambda (x y) y) 1 2)" => "2"; "lambda application")]
+#[test_case("((lambda args args) 1 2 3)" => "(1 2 3)"; "variadic lambda")]
+#[test_case("((lambda (a . b) b) 1 2 3)" => "(2 3)"; "dotted lambda")]
+#[test_case("(define (f x) x) (f 7)" => "7"; "procedure definition")]
+#[test_case("(define (k x) (lambda (y) x)) ((k 1) 2)" => "1"; "closure captures environment")]
+#[test_case("(define x 1) (define (f) x) (define (g x) (f)) (g 2)" => "1"; "lexical scoping")]
+#[test_case("(define (f) (define y 3) y) (f)" => "3"; "internal definition")]
+#[test_case("(define (counter) (define n 0) (lambda () (set! n 1) n)) ((counter))" => "1"; "set! on captured variable")]
+#[test_case("(define (f x) x) f" => "#<procedure f>"; "named procedure")]
+#[test_case("(define f (lambda (x) x)) f" => "#<procedure f>"; "procedure named by define")]
+#[test_case("(lambda (x) x)" => "#<procedure>"; "anonymous procedure")]
+#[test_case("car" => "#<procedure car>"; "primitive procedure")]
+#[test_case("(cons 1 2)" => "(1 . 2)"; "primitive application")]
+#[test_case("(car (cdr (list 1 2 3)))" => "2"; "nested primitive application")]
+#[test_case("(define first car) (first (quote (a b)))" => "a"; "primitive as value")]
+#[test_case("(car (list car))" => "#<procedure car>"; "primitive in list")]
+#[test_case("(not #f)" => "#t"; "not false")]
+#[test_case("(not 0)" => "#f"; "not true value")]
+#[test_case("(boolean? #f)" => "#t"; "boolean predicate")]
+#[test_case("(null? (quote ()))" => "#t"; "null predicate")]
+#[test_case("(pair? (quote ()))" => "#f"; "pair predicate")]
+#[test_case("(procedure? car)" => "#t"; "procedure predicate")]
+#[test_case("(char? #\\a)" => "#t"; "char predicate")]
+#[test_case("(char->integer #\\A)" => "65"; "char to integer")]
+#[test_case("(integer->char 955)" => "#\\\u{3bb}"; "integer to char")]
+#[test_case("\"abc\"" => "\"abc\""; "string self-evaluates")]
+#[test_case("(string-length \"h\\x3bb;llo\")" => "5"; "string length counts characters")]
+#[test_case("(string-ref \"abc\" 1)" => "#\\b"; "string ref")]
+#[test_case("(define s (make-string 3 #\\a)) (string-set! s 1 #\\b) s" => "\"aba\""; "string set")]
+#[test_case("(define s \"abc\") (define t (string-copy s)) (string-set! t 0 #\\z) s" => "\"abc\""; "string copy is independent")]
+#[test_case("(substring \"hello\" 1 3)" => "\"el\""; "substring")]
+#[test_case("(string-append \"a\" \"bc\" \"\")" => "\"abc\""; "string append")]
+#[test_case("(string #\\a #\\\")" => "\"a\\\"\""; "string from characters")]
+#[test_case("(string->symbol \"hello world\")" => "|hello world|"; "string to symbol")]
+#[test_case("(symbol->string (quote abc))" => "\"abc\""; "symbol to string")]
+#[test_case("#(1 a)" => "#(1 a)"; "vector self-evaluates")]
+#[test_case("(vector-ref #(a b c) 2)" => "c"; "vector ref")]
+#[test_case("(define v (make-vector 2 0)) (vector-set! v 0 'x) v" => "#(x 0)"; "vector set")]
+#[test_case("(vector-length (vector 1 2 3))" => "3"; "vector length")]
+#[test_case("(vector->list #(1 2 3) 1)" => "(2 3)"; "vector to list")]
+#[test_case("(list->vector '(1 2))" => "#(1 2)"; "list to vector")]
+#[test_case("(define v (vector 1 2 3 4)) (vector-fill! v 0 1 3) v" => "#(1 0 0 4)"; "vector fill")]
+#[test_case("(vector-copy #(1 2 3) 1 2)" => "#(2)"; "vector copy")]
+#[test_case("(vector-append #(1) #() #(2 3))" => "#(1 2 3)"; "vector append")]
+#[test_case("(define x 2) `#(1 ,x ,@'(3 4))" => "#(1 2 3 4)"; "quasiquoted vector")]
+#[test_case("#u8(1 2)" => "#u8(1 2)"; "bytevector self-evaluates")]
+#[test_case("(bytevector-u8-ref #u8(5 6 7) 1)" => "6"; "bytevector ref")]
+#[test_case("(define b (make-bytevector 3 1)) (bytevector-u8-set! b 2 255) b" => "#u8(1 1 255)"; "bytevector set")]
+#[test_case("(bytevector-length (bytevector 1 2))" => "2"; "bytevector length")]
+#[test_case("(bytevector-copy #u8(1 2 3) 2)" => "#u8(3)"; "bytevector copy")]
+#[test_case("(bytevector-append #u8(1) #u8(2 3))" => "#u8(1 2 3)"; "bytevector append")]
+#[test_case("(+ 1 2 3)" => "6"; "integer addition")]
+#[test_case("(+)" => "0"; "empty addition")]
+#[test_case("(- 5)" => "-5"; "negation")]
+#[test_case("(- 1 1/2 1/3)" => "1/6"; "rational subtraction")]
+#[test_case("(* 2 0.5)" => "1.0"; "mixed multiplication")]
+#[test_case("(/ 1 3)" => "1/3"; "exact division")]
+#[test_case("(/ 2)" => "1/2"; "reciprocal")]
+#[test_case("(/ 1.0 4)" => "0.25"; "inexact division")]
+#[test_case("(* +i +i)" => "-1"; "imaginary unit squared")]
+#[test_case("(+ 1 +2i)" => "1+2i"; "real plus imaginary")]
+#[test_case("(+ 1.5 +2i)" => "1.5+2.0i"; "inexact real plus exact imaginary")]
+#[test_case("(list (/ 1 +i) (expt 1+i 2) (- 1/2+i 1/2))" => "(0-1i 0+2i 0+1i)"; "exact complex arithmetic")]
+#[test_case("(list (exact 1.5+2.5i) (inexact 1/2+i))" => "(3/2+5/2i 0.5+1.0i)"; "complex exactness conversion")]
+#[test_case("(list (eqv? 1+2i 1+2i) (eqv? 1+2i 1.0+2.0i) (= 1+2i 1.0+2.0i))" => "(#t #f #t)"; "exact complex equivalence")]
+#[test_case("(sqrt -4)" => "0.0+2.0i"; "square root of negative number")]
+#[test_case("(sqrt 1/4)" => "1/2"; "exact square root")]
+#[test_case("(magnitude 3+4i)" => "5"; "complex magnitude")]
+#[test_case("(make-rectangular 1 2)" => "1+2i"; "make rectangular")]
+#[test_case("(real-part (make-polar 2 0))" => "2"; "make polar with exact zero angle")]
+#[test_case("(imag-part 5)" => "0"; "imaginary part of real")]
+#[test_case("(angle -1)" => "3.141592653589793"; "angle of negative real")]
+#[test_case("(exp 0)" => "1.0"; "exponential")]
+#[test_case("(log 8 2)" => "3.0"; "logarithm with base")]
+#[test_case("(atan 1 1)" => "0.7853981633974483"; "two argument arctangent")]
+#[test_case("(list (integer? 2.0) (rational? 1/2) (real? 1+i) (complex? 1+i))" => "(#t #t #f #t)"; "numeric predicates")]
+#[test_case("(list (exact? 1/2) (inexact? 0.5) (exact? +i))" => "(#t #t #t)"; "exactness predicates")]
+#[test_case("(list (= 1 1.0 1/1) (= 1/2 0.5) (= 1+i 1+i))" => "(#t #t #t)"; "numeric equality across exactness")]
+#[test_case("(= 9007199254740993 9007199254740992.0)" => "#f"; "exact comparison beyond f64 precision")]
+#[test_case("(< 9007199254740992.0 9007199254740993)" => "#t"; "exact ordering beyond f64 precision")]
+#[test_case("(list (< 1 2 3) (< 1 3 2) (>= 3 3 1) (> 1/3 0.3333333333333333))" => "(#t #f #t #t)"; "chained ordering")]
+#[test_case("(list (< 1 +nan.0) (= +nan.0 +nan.0) (< -inf.0 -1e308 (* 1e308 0) +inf.0))" => "(#f #f #t)"; "special values in ordering")]
+#[test_case("(list (eqv? 2 2) (eqv? 2 2.0) (eqv? 1/2 2/4) (eqv? +nan.0 +nan.0) (eqv? 0.0 -0.0))" => "(#t #f #t #t #f)"; "eqv on numbers")]
+#[test_case("(list (eq? 'a 'a) (eq? '() '()) (eqv? #\\a #\\a) (eqv? \"a\" \"a\") (eqv? car car))" => "(#t #t #t #f #t)"; "eqv on other values")]
+#[test_case("(define p (cons 1 2)) (list (eqv? p p) (eqv? p (cons 1 2)) (equal? p (cons 1 2)))" => "(#t #f #t)"; "eqv and equal on pairs")]
+#[test_case("(equal? '(1 #(2 \"x\") #u8(3)) (list 1 (vector 2 \"x\") (bytevector 3)))" => "#t"; "equal on nested structures")]
+#[test_case("(list (equal? 2 2.0) (equal? \"a\" \"b\"))" => "(#f #f)"; "equal distinguishes values")]
+#[test_case("(define a (vector 1 2)) (define b (vector 1 2)) (vector-set! a 1 a) (vector-set! b 1 b) (equal? a b)" => "#t"; "equal on circular vectors")]
+#[test_case("(define a (vector 1 #f)) (define b (vector 1 #f)) (vector-set! a 1 (list 2 a)) (vector-set! b 1 (list 2 (vector 1 (list 2 b)))) (equal? a b)" => "#t"; "equal on circular structures of different periods")]
+#[test_case("(define a (vector 1 #f)) (define b (vector 2 #f)) (vector-set! a 1 a) (vector-set! b 1 b) (equal? a b)" => "#f"; "equal on unequal circular vectors")]
+#[test_case("(list (quotient 17 -5) (remainder 17 -5) (modulo 17 -5) (modulo -7 2.0))" => "(-3 2 -3 1.0)"; "integer division")]
+#[test_case("(list (call-with-values (lambda () (floor/ -7 2)) list) (call-with-values (lambda () (truncate/ -7 2)) list))" => "((-4 1) (-3 -1))"; "division with remainder")]
+#[test_case("(let-values (((q r) (floor/ 7 2))) (list q r))" => "(3 1)"; "division values bound by let-values")]
+#[test_case("(list (gcd) (gcd 32 -36) (lcm) (lcm 32 -36) (lcm 4 6.0))" => "(0 4 1 288 12.0)"; "gcd and lcm")]
+#[test_case("(list (abs -1/2) (min 1 2.0) (max 3 1/2) (square 1/3))" => "(1/2 1.0 3 1/9)"; "abs min max and square")]
+#[test_case("(list (expt 2 -2) (expt 2.0 3) (expt 0 0) (expt 0.0 0))" => "(1/4 8.0 1 1)"; "exponentiation")]
+#[test_case("(call-with-values (lambda () (exact-integer-sqrt 17)) list)" => "(4 1)"; "exact integer square root")]
+#[test_case("(list (floor -4.3) (ceiling 7/2) (round 5/2) (round 3.5) (truncate -7/2))" => "(-5.0 4 2 4.0 -3)"; "rounding")]
+#[test_case("(list (exact 2.5) (exact 3.0) (inexact 1/4) (exact->inexact 1))" => "(5/2 3 0.25 1.0)"; "exactness conversion")]
+#[test_case("(list (zero? 0.0) (positive? +nan.0) (negative? -1/2) (odd? 3) (even? 4.0) (exact-integer? 4.0))" => "(#t #f #t #t #t #f)"; "sign and parity predicates")]
+#[test_case("(list (number->string 255 16) (number->string -3/4 2) (number->string 1.5) (number->string 1+2i) (number->string 3-4i 2))" => "(\"ff\" \"-11/100\" \"1.5\" \"1+2i\" \"11-100i\")"; "number to string")]
+#[test_case("(list (string->number \"ff\" 16) (string->number \"#b101\" 16) (string->number \"-11/100\" 2) (string->number \"1e2\"))" => "(255 5 -3/4 100.0)"; "string to number")]
+#[test_case("(list (string->number \"#e1.5\") (string->number \"+inf.0\") (string->number \"1+i\"))" => "(3/2 +inf.0 1+1i)"; "string to number with prefixes and special values")]
+#[test_case("(list (string->number \"abc\") (string->number \"1 \") (string->number \"12\" 2) (string->number \"\"))" => "(#f #f #f #f)"; "string to number failure")]
+#[test_case("(string->number (number->string 12345678901234567890/7 16) 16)" => "12345678901234567890/7"; "number string round trip")]
+#[test_case("(list (bitwise-and) (bitwise-and 12 10) (bitwise-ior 12 10) (bitwise-xor 12 10 1) (bitwise-not 5))" => "(-1 8 14 7 -6)"; "bitwise logic")]
+#[test_case("(list (bitwise-and -1 255) (bitwise-ior -8 3) (bitwise-xor -1 5))" => "(255 -5 -6)"; "bitwise logic on negative integers")]
+#[test_case("(bitwise-and (- (expt 2 100) 1) (expt 2 99))" => "633825300114114700748351602688"; "bitwise logic on big integers")]
+#[test_case("(list (arithmetic-shift 1 70) (arithmetic-shift 255 -4) (arithmetic-shift -5 -1))" => "(1180591620717411303424 15 -3)"; "arithmetic shift")]
+#[test_case("(list (bit-count 7) (bit-count -8) (bit-count 0) (bit-count (expt 2 80)))" => "(3 3 0 1)"; "bit count")]
+#[test_case("(list (integer-length 0) (integer-length 255) (integer-length -256) (integer-length -257))" => "(0 8 8 9)"; "integer length")]
+#[test_case("(list (bit-set? 0 5) (bit-set? 1 5) (bit-set? 100 -1) (bit-set? 100 1))" => "(#t #f #t #f)"; "bit set")]
+#[test_case("(list (arithmetic-shift 5 (- (expt 2 100))) (arithmetic-shift -5 (- (expt 2 100))) (arithmetic-shift 0 (expt 2 100)))" => "(0 -1 0)"; "arithmetic shift by huge counts")]
+#[test_case("(list (bit-field 5 0 (expt 2 62)) (bit-field -1 70 72))" => "(5 3)"; "wide bit field")]
+#[test_case("(list (bit-field #b1101101010 0 4) (bit-field #b1101101010 4 9) (bit-field -1 3 6))" => "(10 22 7)"; "bit field")]
+#[test_case("(let ((x 1) (y 2)) (define z 3) (+ x y z))" => "6"; "let with internal definition")]
+#[test_case("(define x 1) (let ((x 2) (y x)) y)" => "1"; "let inits use outer scope")]
+#[test_case("(let* ((x 1) (y (+ x 1))) (* x y))" => "2"; "let star")]
+#[test_case("(let* () 5)" => "5"; "empty let star")]
+#[test_case("(letrec ((even? (lambda (n) (if (= n 0) #t (odd? (- n 1))))) (odd? (lambda (n) (if (= n 0) #f (even? (- n 1)))))) (even? 100))" => "#t"; "letrec")]
+#[test_case("(letrec* ((a 1) (b (+ a 1))) (list a b))" => "(1 2)"; "letrec star")]
+#[test_case("(let loop ((i 0) (acc '())) (if (= i 3) acc (loop (+ i 1) (cons i acc))))" => "(2 1 0)"; "named let")]
+#[test_case("(let loop ((i 0)) loop)" => "#<procedure loop>"; "named let procedure")]
+#[test_case("(let-values (((a) 1) ((b . c) 2)) (list a b c))" => "(1 2 ())"; "let values")]
+#[test_case("(let-values ((all 1) ((x) 2)) (list all x))" => "((1) 2)"; "let values with rest")]
+#[test_case("(define x 1) (let-values (((x) 2) ((y) x)) y)" => "1"; "let values inits use outer scope")]
+#[test_case("(list (and) (and 1 2) (and #f (car '())) (or) (or #f 2) (or 1 (car '())))" => "(#t 2 #f #f 2 1)"; "and and or")]
+#[test_case("(define t 5) (or #f t)" => "5"; "or does not capture variables")]
+#[test_case("(list (when (= 1 1) 'a 'b) (unless (= 1 1) 'c) (unless #f 'd))" => "(b #<unspecified> d)"; "when and unless")]
+#[test_case("(define (sign n) (cond ((< n 0) 'negative) ((= n 0) 'zero) (else 'positive))) (list (sign -1) (sign 0) (sign 1))" => "(negative zero positive)"; "cond")]
+#[test_case("(list (cond ((cdr '(a . b)) => symbol->string) (else 'none)) (cond (#f 1) (2)) (cond (#f 1)))" => "(\"b\" 2 #<unspecified>)"; "cond with arrow and test only clauses")]
+#[test_case("(define k 10) (cond (1 => (lambda (v) (+ v k))))" => "11"; "cond arrow does not capture variables")]
+#[test_case("(define (kind x) (case x ((1 2 3) 'small) ((a b) 'letter) (else 'other))) (list (kind 2) (kind 'b) (kind 9))" => "(small letter other)"; "case")]
+#[test_case("(list (case (* 2 3) ((6) => (lambda (x) (* x x)))) (case 'z ((a) 1) (else => symbol->string)) (case 1 ((2) 'no)))" => "(36 \"z\" #<unspecified>)"; "case with arrow")]
+#[test_case("(define key 'outer) (case 1 ((1) key))" => "outer"; "case does not capture variables")]
+#[test_case("(do ((i 0 (+ i 1)) (acc '() (cons i acc))) ((= i 3) acc))" => "(2 1 0)"; "do loop")]
+#[test_case("(define v (make-vector 3)) (do ((i 0 (+ i 1))) ((= i 3) v) (vector-set! v i (* i i)))" => "#(0 1 4)"; "do loop with commands")]
+#[test_case("(do ((i 0 (+ i 1))) ((= i 2)))" => "#<unspecified>"; "do loop without result")]
+#[test_case("(let loop ((n 1000000) (acc 0)) (if (= n 0) acc (loop (- n 1) (+ acc 1))))" => "1000000"; "tail call in named let")]
+#[test_case("(define (count n acc) (if (= n 0) acc (count (- n 1) (+ acc 1)))) (count 100000 0)" => "100000"; "tail call in if")]
+#[test_case("(define (count n) (cond ((= n 0) 'done) (else (let ((m (- n 1))) (and #t (or #f (when #t (count m)))))))) (count 100000)" => "done"; "tail calls in derived forms")]
+#[test_case("(do ((i 0 (+ i 1))) ((= i 100000) i))" => "100000"; "tail call in do")]
+#[test_case("(define (count n) (begin 'ignored (if (= n 0) 'done (begin (count (- n 1)))))) (count 100000)" => "done"; "tail call in begin")]
+#[test_case("(define (ping n) (if (= n 0) 'ping (pong (- n 1)))) (define (pong n) (if (= n 0) 'pong (ping (- n 1)))) (ping 100001)" => "pong"; "mutual tail recursion")]
+#[test_case("(define (count n) (define next (- n 1)) (if (< next 0) n (count next))) (count 100000)" => "0"; "tail call after internal define")]
+#[test_case("(+ 1 (call/cc (lambda (k) (+ 10 (k 2)))))" => "3"; "escaping continuation")]
+#[test_case("(call-with-current-continuation (lambda (k) 5))" => "5"; "unused continuation")]
+#[test_case("(list (call/cc procedure?) (call/cc (lambda (k) k)))" => "(#t #<continuation>)"; "continuation is a procedure")]
+#[test_case("(let ((n 0) (k #f)) (let ((r (call/cc (lambda (c) (set! k c) 0)))) (set! n (+ n 1)) (if (< r 3) (k (+ r 1)) (list n r))))" => "(4 3)"; "reentered continuation")]
+#[test_case("(define k #f) (define count 0) (+ 1 (call/cc (lambda (c) (set! k c) 1))) (set! count (+ count 1)) (if (< count 3) (k count) 'done)" => "2"; "continuation of earlier expression")]
+#[test_case("(define (count n) (if (= n 0) 0 (+ 1 (count (- n 1))))) (count 100000)" => "100000"; "deep recursion")]
+#[test_case("(define trace '()) (define (note x) (set! trace (cons x trace))) (let ((r (dynamic-wind (lambda () (note 'before)) (lambda () (note 'during) 'result) (lambda () (note 'after))))) (list r trace))" => "(result (after during before))"; "dynamic wind")]
+#[test_case("(define trace '()) (define (note x) (set! trace (cons x trace))) (let ((r (call/cc (lambda (k) (dynamic-wind (lambda () (note 'in)) (lambda () (k 'escaped)) (lambda () (note 'out))))))) (list r trace))" => "(escaped (out in))"; "escape from dynamic wind")]
+#[test_case("(define trace '()) (define (note x) (set! trace (cons x trace))) (call/cc (lambda (k) (dynamic-wind (lambda () (note 'a-in)) (lambda () (dynamic-wind (lambda () (note 'b-in)) (lambda () (k 'x)) (lambda () (note 'b-out)))) (lambda () (note 'a-out))))) trace" => "(a-out b-out b-in a-in)"; "escape from nested dynamic wind")]
+#[test_case("(define trace '()) (define (note x) (set! trace (cons x trace))) (let ((k #f) (n 0)) (dynamic-wind (lambda () (note 'in)) (lambda () (call/cc (lambda (c) (set! k c))) (set! n (+ n 1))) (lambda () (note 'out))) (if (< n 3) (k #f) trace))" => "(out in out in out in)"; "reenter dynamic wind")]
+#[test_case("(define trace '()) (define (note x) (set! trace (cons x trace))) (define k #f) (dynamic-wind (lambda () (note 'a-in)) (lambda () (call/cc (lambda (c) (set! k c)))) (lambda () (note 'a-out))) (define n 0) (dynamic-wind (lambda () (note 'b-in)) (lambda () (set! n (+ n 1)) (if (= n 1) (k #f))) (lambda () (note 'b-out))) trace" => "(a-out a-in b-out b-in a-out a-in)"; "jump between dynamic winds")]
+#[test_case("(guard (e (#t (list 'caught e))) (raise 'oops))" => "(caught oops)"; "guard catches raise")]
+#[test_case("(guard (e ((number? e) 'number) ((string? e) 'string)) (raise \"x\"))" => "string"; "guard clauses")]
+#[test_case("(guard (e ((and (pair? e) (car e)) => (lambda (x) (* x 2)))) (raise (list 21)))" => "42"; "guard with arrow")]
+#[test_case("(guard (e (#t 'caught)) (define x 1) (+ x 2))" => "3"; "guard body value")]
+#[test_case("(let ((result 5) (condition 6)) (guard (e (#t (+ condition e))) (raise result)))" => "11"; "guard is hygienic")]
+#[test_case("(guard (e (#t (list 'outer e))) (guard (e ((string? e) 'inner)) (raise 'sym)))" => "(outer sym)"; "guard reraises unmatched")]
+#[test_case("(with-exception-handler (lambda (e) 10) (lambda () (+ 1 (guard (e ((string? e) 'no)) (raise-continuable 'x)))))" => "11"; "guard reraises continuably")]
+#[test_case("(define (f) (guard (e (#t e)) (raise 'x))) (define raise-continuable #f) (define call-with-current-continuation #f) (f)" => "x"; "guard with rebound globals")]
+#[test_case("(guard (e ((error-object? e) (error-object-message e))) (car 5))" => "\"car: expected pair, got 5\""; "guard catches evaluator error")]
+#[test_case("(guard (e (#t (error-object-irritants e))) (vector-ref 'a 0))" => "(a)"; "evaluator error irritants")]
+#[test_case("(guard (e ((error-object? e) (list (error-object-message e) (error-object-irritants e)))) (error \"bad thing:\" 1 2))" => "(\"bad thing:\" (1 2))"; "error object")]
+#[test_case("(guard (e (#t (list (error-object? e) (read-error? e) (file-error? e)))) (error \"x\"))" => "(#t #f #f)"; "error object predicates")]
+#[test_case("(list (error-object? 'x) (read-error? 'x) (file-error? 'x))" => "(#f #f #f)"; "error predicates on other objects")]
+#[test_case("(guard (e (#t e)) (error \"bad:\" 'x \"y\"))" => "#<error \"bad:\" x \"y\">"; "error object display")]
+#[test_case("(with-exception-handler (lambda (c) 42) (lambda () (+ (raise-continuable 'oops) 1)))" => "43"; "raise continuable")]
+#[test_case("(call/cc (lambda (k) (with-exception-handler (lambda (e) (k (list 'handled e))) (lambda () (raise 'boom)))))" => "(handled boom)"; "handler escapes with continuation")]
+#[test_case("(with-exception-handler (lambda (e) (* e 2)) (lambda () (with-exception-handler (lambda (e) (raise-continuable (+ e 1))) (lambda () (raise-continuable 1)))))" => "4"; "handler runs with outer handlers")]
+#[test_case("(define r (with-exception-handler (lambda (e) 0) (lambda () 1))) (guard (e (#t (list r e))) (raise 'x))" => "(1 x)"; "handler removed after thunk returns")]
+#[test_case("(define trace '()) (define (note x) (set! trace (cons x trace))) (guard (e (#t trace)) (dynamic-wind (lambda () (note 'in)) (lambda () (raise 'x)) (lambda () (note 'out))))" => "(out in)"; "guard runs after thunks")]
+#[test_case("(guard (e ((error-object? e) (error-object-message e))) (with-exception-handler (lambda (e) 'ignored) (lambda () (raise 'x))))" => "\"exception handler returned from non-continuable raise: x\""; "handler returning from raise")]
+#[test_case("(call-with-values (lambda () (values 1 2)) +)" => "3"; "call with values")]
+#[test_case("(call-with-values (lambda () (values)) list)" => "()"; "call with zero values")]
+#[test_case("(call-with-values (lambda () 5) list)" => "(5)"; "call with single value")]
+#[test_case("(values 1 \"two\" 'three)" => "1 \"two\" three"; "multiple values")]
+#[test_case("(values)" => ""; "zero values")]
+#[test_case("(values 1)" => "1"; "values of one")]
+#[test_case("(let-values (((a b) (values 1 2)) ((c . d) (values 3 4 5))) (list a b c d))" => "(1 2 3 (4 5))"; "let values with multiple values")]
+#[test_case("(let*-values (((a b) (values 1 2)) ((c) (values (+ a b)))) (list a b c))" => "(1 2 3)"; "let star values")]
+#[test_case("(receive (q . r) (values 1 2 3) (list q r))" => "(1 (2 3))"; "receive")]
+#[test_case("(define-values (x y) (values 1 2)) (list x y)" => "(1 2)"; "define values")]
+#[test_case("(define-values (x . y) (values 1 2 3)) (list x y)" => "(1 (2 3))"; "define values with rest")]
+#[test_case("(define-values all (values 1 2)) all" => "(1 2)"; "define values with single formal")]
+#[test_case("(define-values () (values)) 'ok" => "ok"; "define values with no formals")]
+#[test_case("(define (f) (define-values (a b) (values 1 2)) (define c 3) (+ a b c)) (f)" => "6"; "internal define values")]
+#[test_case("(let ((t0 5) (rest 6)) (define-values (a . b) (values t0 rest)) (list a b))" => "(5 (6))"; "define values is hygienic")]
+#[test_case("(call/cc (lambda (k) (k 1 2)))" => "1 2"; "continuation returns multiple values")]
+#[test_case("(call-with-values (lambda () (call/cc (lambda (k) (k 1 2)))) list)" => "(1 2)"; "continuation with multiple values")]
+#[test_case("(call-with-values (lambda () (dynamic-wind (lambda () #f) (lambda () (values 1 2)) (lambda () #f))) list)" => "(1 2)"; "dynamic wind returns multiple values")]
+#[test_case("(call-with-values (lambda () (guard (e (#t 0)) (values 1 2))) list)" => "(1 2)"; "guard returns multiple values")]
+#[test_case("(guard (e ((file-error? e) (list (read-error? e) (error-object-irritants e)))) (load \"/nonexistent/zed.scm\"))" => "(#f (\"/nonexistent/zed.scm\"))"; "load of missing file")]
+#[test_case("(list (expt 1 (expt 10 12)) (expt -1 (+ (expt 10 12) 1)) (expt 0 (expt 10 12)) (expt 10. (expt 10 12)))" => "(1 -1 0 +inf.0)"; "expt with huge powers")]
+fn evaluate(text: &str) -> String {
+    let mut evaluator = ExprEvaluator::new();
+    let mut result = None;
+
+    for expr in parse_source(&Source::new("test", text)).unwrap() {
+        match evaluator.eval(expr) {
+            Ok(value) => result = Some(value),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    result.unwrap().to_string()
+}
+
+#[test_case("undefined"; "unbound variable")]
+#[test_case("(set! undefined 1)"; "set! of unbound variable")]
+#[test_case("((lambda (x) x))"; "too few arguments")]
+#[test_case("((lambda (x) x) 1 2)"; "too many arguments")]
+#[test_case("(1 2)"; "non-procedure application")]
+#[test_case("()"; "empty combination")]
+#[test_case("(quote)"; "malformed quote")]
+#[test_case("`(,@1)"; "splicing a non-list")]
+#[test_case("(/ 1 0)"; "exact division by zero")]
+#[test_case("(+ 1 'a)"; "addition of non-number")]
+#[test_case("(make-rectangular +i 1)"; "complex rectangular part")]
+#[test_case("(vector-ref #(1) 1)"; "vector index out of range")]
+#[test_case("(vector-ref '(1) 0)"; "vector ref of list")]
+#[test_case("(bytevector-u8-set! (make-bytevector 1) 0 256)"; "byte out of range")]
+#[test_case("(car 1)"; "primitive type error")]
+#[test_case("(cons 1)"; "primitive arity error")]
+#[test_case("(string-ref \"abc\" 3)"; "string index out of range")]
+#[test_case("(substring \"abc\" 2 1)"; "invalid substring range")]
+#[test_case("(lambda (1) 1)"; "invalid parameter")]
+#[test_case("(< 1 +i)"; "ordering of complex numbers")]
+#[test_case("(= 1 'a)"; "comparison of non-number")]
+#[test_case("(quotient 1 0)"; "integer division by zero")]
+#[test_case("(modulo 1.5 1)"; "modulo of non-integer")]
+#[test_case("(expt 0 -1)"; "exact zero to negative power")]
+#[test_case("(exact +inf.0)"; "exact infinity")]
+#[test_case("(exact-integer-sqrt -1)"; "negative exact integer square root")]
+#[test_case("(round 1+i)"; "rounding complex number")]
+#[test_case("(number->string 1.5 2)"; "inexact number in binary")]
+#[test_case("(number->string 10 3)"; "unsupported radix")]
+#[test_case("(string->number 'a)"; "string to number of symbol")]
+#[test_case("(bitwise-and 1 1.0)"; "bitwise logic on inexact integer")]
+#[test_case("(bit-field 5 3 1)"; "invalid bit field range")]
+#[test_case("(bit-set? -1 5)"; "negative bit index")]
+#[test_case("(let ((x)) x)"; "let binding without value")]
+#[test_case("(let ((x 1)))"; "let without body")]
+#[test_case("(let-values (((a b) 1)) a)"; "let values count mismatch")]
+#[test_case("(cond (else 1) (#t 2))"; "cond else not last")]
+#[test_case("(case 1 (1 'one))"; "case clause without datum list")]
+#[test_case("(do ((i 0 1 2)) (#t))"; "malformed do binding")]
+fn evaluate_error(text: &str) {
+    let mut evaluator = ExprEvaluator::new();
+
+    for expr in parse_source(&Source::new("test", text)).unwrap() {
+        if evaluator.eval(expr).is_err() {
+            return;
+        }
+    }
+
+    panic!("expected error evaluating {}", text);
+}
+
+#[test_case("(define x 1)\n(+ x y)" => "unbound variable: y (at test:2:6)"; "unbound variable")]
+#[test_case("(car 5)" => "car: expected pair, got 5 (at test:1:1)"; "wrong type")]
+#[test_case("(define (f x) x)\n(f)" => "#<procedure f>: wrong number of arguments: expected 1, got 0 (at test:2:1)"; "arity mismatch")]
+#[test_case("(define (f x) (/ x 0))\n(f 1)" => "/: division by zero (at test:1:15)"; "division by zero inside procedure")]
+#[test_case("(vector-ref #(1 2) 2)" => "vector-ref: index 2 out of range for length 2 (at test:1:1)"; "index out of range")]
+#[test_case("(raise 'oops)" => "uncaught exception: oops (at test:1:1)"; "user raise")]
+#[test_case("(5 1)" => "not a procedure: 5 (at test:1:1)"; "non-procedure application")]
+#[test_case("(if)" => "bad syntax in if: () (at test:1:1)"; "bad syntax")]
+#[test_case("(let ((x 1))\n  (let ((y (car x))) y))" => "car: expected pair, got 1 (at test:2:12)"; "error inside derived form")]
+#[test_case("(call/cc 5)" => "not a procedure: 5 (at test:1:1)"; "continuation receiver is not a procedure")]
+#[test_case("(dynamic-wind (lambda () 1) (lambda () 2))" => "#<procedure dynamic-wind>: wrong number of arguments: expected 3, got 2 (at test:1:1)"; "dynamic wind arity")]
+#[test_case("(error \"something bad:\" 42)" => "something bad: 42 (at test:1:1)"; "uncaught error")]
+#[test_case("(guard (e ((string? e) 'no)) (car 5))" => "car: expected pair, got 5 (at test:1:30)"; "uncaught evaluator error in guard")]
+#[test_case("(with-exception-handler 5 (lambda () 1))" => "with-exception-handler: expected procedure, got 5 (at test:1:1)"; "handler is not a procedure")]
+#[test_case("(error 'oops)" => "error: expected string, got oops (at test:1:1)"; "error without message")]
+#[test_case("(guard (e) (raise 'x))" => "uncaught exception: x (at test:1:1)"; "guard without clauses")]
+#[test_case("(define-values (a b) (values 1 2 3))" => "define-values: wrong number of arguments: expected 2, got 3 (at test:1:1)"; "define values count mismatch")]
+#[test_case("(call-with-values (lambda () (values 1 2)) car)" => "#<procedure car>: wrong number of arguments: expected 1, got 2 (at test:1:1)"; "consumer arity")]
+#[test_case("(if (define-values (a) 1) 2)" => "bad syntax in expression context: (define-values (a) 1) (at test:1:5)"; "define values in expression")]
+#[test_case("(arithmetic-shift 1 100000000)" => "arithmetic-shift: expected operand giving a result of at most 16777216 bits, got 100000000 (at test:1:1)"; "arithmetic shift too large")]
+#[test_case("(bit-field -1 0 100000000)" => "bit-field: expected operand giving a result of at most 16777216 bits, got 100000000 (at test:1:1)"; "bit field too wide")]
+#[test_case("(make-vector 100000000)" => "make-vector: index 100000000 out of range for length 16777216 (at test:1:1)"; "make vector too long")]
+#[test_case("(make-bytevector (expt 2 62) 0)" => "make-bytevector: index 4611686018427387904 out of range for length 16777216 (at test:1:1)"; "make bytevector too long")]
+#[test_case("(make-string 100000000 #\\a)" => "make-string: index 100000000 out of range for length 16777216 (at test:1:1)"; "make string too long")]
+#[test_case("(expt 10 (expt 10 12))" => "expt: expected operand giving a result of at most 16777216 bits, got 1000000000000 (at test:1:1)"; "exact expt too large")]
+#[test_case("(expt +i (expt 10 12))" => "expt: expected operand giving a result of at most 16777216 bits, got 1000000000000 (at test:1:1)"; "exact complex expt too large")]
+#[test_case("(expt 1/2 (- (expt 10 12)))" => "expt: expected operand giving a result of at most 16777216 bits, got -1000000000000 (at test:1:1)"; "exact expt of rational too large")]
+fn error_message(text: &str) -> String {
+    let mut evaluator = ExprEvaluator::new();
+
+    for expr in parse_source(&Source::new("test", text)).unwrap() {
+        if let Err(err) = evaluator.eval(expr) {
+            return err.to_string();
+        }
+    }
+
+    panic!("expected error evaluating {}", text);
+}
+
+#[test]
+fn error_kinds() {
+    use std::rc::Rc;
+
+    use crate::data::Expression;
+    use crate::eval::EvalErrorKind::*;
+
+    let mut evaluator = ExprEvaluator::new();
+    let mut eval = |text: &str| {
+        for expr in parse_source(&Source::new("test", text)).unwrap() {
+            if let Err(err) = evaluator.eval(expr) {
+                return err;
+            }
+        }
+        panic!("expected error evaluating {}", text);
+    };
+
+    assert!(matches!(eval("undefined").kind, UnboundVariable(name) if name == "undefined"));
+    assert!(matches!(eval("(string-length 'a)").kind,
+        WrongType { procedure, expected, actual } if procedure == "string-length" && expected == "string" && *actual == Expression::Symbol("a".into())));
+    assert!(matches!(eval("(cons 1)").kind, Arity { min_args: 2, max_args: Some(2), actual: 1, .. }));
+    assert!(matches!(eval("(quotient 1 0)").kind, DivisionByZero(name) if name == "quotient"));
+    assert!(matches!(eval("(string-ref \"ab\" 5)").kind, IndexOutOfRange { index: 5, length: 2, .. }));
+    assert!(matches!(eval("(raise (list 1 2))").kind, Raise(payload) if payload.to_string() == "(1 2)"));
+    assert!(matches!(eval("(with-exception-handler (lambda (e) 0) (lambda () (raise 'x)))").kind,
+        NonContinuable(payload) if payload.to_string() == "x"));
+    assert!(matches!(eval("(let-values (((a b) 1)) a)").kind,
+        Arity { procedure, min_args: 2, max_args: Some(2), actual: 1 } if procedure.to_string() == "let-values"));
+    assert!(matches!(eval("(define-values (a . b) (values))").kind,
+        Arity { procedure, min_args: 1, max_args: None, actual: 0 } if procedure.to_string() == "define-values"));
+    assert!(matches!(eval("(arithmetic-shift 1 (expt 2 100))").kind, WrongType { procedure, .. } if procedure == "arithmetic-shift"));
+    assert!(matches!(eval("(bit-field 5 3 1)").kind, IndexOutOfRange { index: 3, length: 1, .. }));
+    assert!(matches!(eval("(number->string 1.5 2)").kind, WrongType { procedure, .. } if procedure == "number->string"));
+    assert!(matches!(eval("(define-syntax f (syntax-rules () ((_ (a ...) (b ...)) '((a b) ...)))) (f (1 2) (3))").kind,
+        BadSyntax { .. }));
+
+    let err = ExprEvaluator::new().run_command("other", Rc::new(Expression::Nil)).unwrap_err();
+    assert!(matches!(err.kind, UnknownCommand(name) if name == "other"));
+
+    let err = eval("(begin 1\n  (car '()))");
+    assert_eq!(err.expr.as_ref().map(|e| e.to_string()), Some("(car '())".to_string()));
+    assert_eq!(err.span().map(|s| (s.line(), s.column())), Some((2, 3)));
+}
+
+
+#[test]
+fn load() {
+    let dir = std::env::temp_dir();
+    let good = dir.join(format!("zed-load-{}.scm", std::process::id()));
+    let bad = dir.join(format!("zed-load-bad-{}.scm", std::process::id()));
+    let control = dir.join(format!("zed-load-control-{}.scm", std::process::id()));
+    std::fs::write(&good, "(define loaded 41)\n(define (next) (+ loaded 1))\n").unwrap();
+    std::fs::write(&bad, "(define loaded\n  (+ 1 2\n").unwrap();
+    std::fs::write(&control, "(define-syntax twice (syntax-rules () ((_ e) (* 2 e))))\n\
+        (set! trace (cons (twice (raise-continuable 'ask)) trace))\n\
+        (escape 'escaped)\n\
+        (set! trace (cons 'unreached trace))\n").unwrap();
+
+    let mut evaluator = ExprEvaluator::new();
+    let mut eval = |text: String| {
+        let mut result = None;
+        for expr in parse_source(&Source::new("test", &text)).unwrap() {
+            result = Some(evaluator.eval(expr).map(|v| v.to_string()).map_err(|e| e.to_string()));
+        }
+        result.unwrap()
+    };
+
+    assert_eq!(eval(format!("(load {:?}) (next)", good)), Ok("42".to_string()));
+    assert_eq!(eval(format!("(guard (e ((read-error? e) (file-error? e))) (load {:?}))", bad)), Ok("#f".to_string()));
+    assert!(eval(format!("(load {:?})", bad)).unwrap_err().starts_with("load: "));
+    assert_eq!(
+        eval(format!("(define trace '()) (define escape #f) \
+            (define result (call/cc (lambda (k) \
+              (set! escape k) \
+              (with-exception-handler (lambda (e) 21) \
+                (lambda () (dynamic-wind (lambda () #f) (lambda () (load {:?})) (lambda () (set! trace (cons 'after trace))))))))) \
+            (list result trace)", control)),
+        Ok("(escaped (after 42))".to_string())
+    );
+
+    std::fs::remove_file(good).unwrap();
+    std::fs::remove_file(bad).unwrap();
+    std::fs::remove_file(control).unwrap();
+}
@@ -1,7 +1,19 @@
+mod derived;
+mod environment;
 mod error;
+mod expander;
+#[allow(clippy::module_inception)]
 mod eval;
 mod expr_evaluator;
+mod primitives;
+mod syntax_rules;
 
+pub use self::environment::*;
 pub use self::error::*;
 pub use self::eval::*;
+pub use self::expander::Scope;
 pub use self::expr_evaluator::*;
+pub use self::syntax_rules::SyntaxRules;
+
+mod expander_tests;
+mod expr_evaluator_tests;
@@ -0,0 +1,129 @@
+use std::rc::Rc;
+
+use num::{BigInt, One, Signed, ToPrimitive, Zero};
+
+use crate::data::{Expr, Expression, Number};
+use crate::eval::{Env, EvalResult};
+use crate::eval::primitives::{boolean, define, index, integer, out_of_range, too_large, wrong_type, MAX_INTEGER_BITS};
+
+pub fn register(env: &Env) {
+    define(env, "bitwise-not", 1, Some(1), bitwise_not);
+    define(env, "bitwise-and", 0, None, bitwise_and);
+    define(env, "bitwise-ior", 0, None, bitwise_ior);
+    define(env, "bitwise-xor", 0, None, bitwise_xor);
+    define(env, "arithmetic-shift", 2, Some(2), arithmetic_shift);
+    define(env, "bit-count", 1, Some(1), bit_count);
+    define(env, "integer-length", 1, Some(1), integer_length);
+    define(env, "bit-set?", 2, Some(2), is_bit_set);
+    define(env, "bit-field", 3, Some(3), bit_field);
+}
+
+fn new_integer(value: BigInt) -> Expr {
+    Rc::new(Expression::Number(Number::Integer(value)))
+}
+
+fn exact_integer<'a>(name: &str, arg: &'a Expr) -> EvalResult<&'a BigInt> {
+    match &**arg {
+        Expression::Number(Number::Integer(n)) => Ok(n),
+        _ => Err(wrong_type(name, "exact integer", arg)),
+    }
+}
+
+fn fold(name: &str, args: &[Expr], identity: BigInt, op: fn(BigInt, &BigInt) -> BigInt) -> EvalResult<Expr> {
+    let mut result = identity;
+
+    for arg in args {
+        result = op(result, exact_integer(name, arg)?);
+    }
+
+    Ok(new_integer(result))
+}
+
+/// Returns the bitwise NOT of `n`, which is `-n - 1`: every bit of its two's complement
+/// representation flipped.
+fn complement(n: &BigInt) -> BigInt {
+    -n - BigInt::one()
+}
+
+fn bitwise_not(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_integer(complement(exact_integer("bitwise-not", &args[0])?)))
+}
+
+fn bitwise_and(args: &[Expr]) -> EvalResult<Expr> {
+    fold("bitwise-and", args, -BigInt::one(), |a, b| a & b)
+}
+
+fn bitwise_ior(args: &[Expr]) -> EvalResult<Expr> {
+    fold("bitwise-ior", args, BigInt::zero(), |a, b| a | b)
+}
+
+fn bitwise_xor(args: &[Expr]) -> EvalResult<Expr> {
+    fold("bitwise-xor", args, BigInt::zero(), |a, b| a ^ b)
+}
+
+fn arithmetic_shift(args: &[Expr]) -> EvalResult<Expr> {
+    let n = exact_integer("arithmetic-shift", &args[0])?;
+    let count = exact_integer("arithmetic-shift", &args[1])?;
+
+    if count.is_negative() {
+        // Shifting right by more bits than `n` has leaves only its sign.
+        return match (-count).to_u64() {
+            Some(amount) if amount < n.bits() => Ok(new_integer(n >> amount)),
+            _ => Ok(integer(if n.is_negative() { -1 } else { 0 })),
+        };
+    }
+
+    if n.is_zero() {
+        return Ok(new_integer(BigInt::zero()));
+    }
+    match count.to_u64() {
+        Some(amount) if n.bits() + amount <= MAX_INTEGER_BITS => Ok(new_integer(n << amount)),
+        _ => Err(too_large("arithmetic-shift", &args[1])),
+    }
+}
+
+fn bit_count(args: &[Expr]) -> EvalResult<Expr> {
+    let n = exact_integer("bit-count", &args[0])?;
+    let bits = if n.is_negative() { complement(n) } else { n.clone() };
+    let count: u32 = bits.magnitude().to_u64_digits().iter().map(|d| d.count_ones()).sum();
+
+    Ok(integer(count))
+}
+
+fn integer_length(args: &[Expr]) -> EvalResult<Expr> {
+    let n = exact_integer("integer-length", &args[0])?;
+    let bits = if n.is_negative() { complement(n).bits() } else { n.bits() };
+
+    Ok(integer(bits))
+}
+
+fn is_bit_set(args: &[Expr]) -> EvalResult<Expr> {
+    let bit = index("bit-set?", &args[0])?;
+    let n = exact_integer("bit-set?", &args[1])?;
+
+    Ok(boolean(((n >> bit) & BigInt::one()).is_one()))
+}
+
+fn bit_field(args: &[Expr]) -> EvalResult<Expr> {
+    let n = exact_integer("bit-field", &args[0])?;
+    let start = index("bit-field", &args[1])?;
+    let end = index("bit-field", &args[2])?;
+
+    if start > end {
+        return Err(out_of_range("bit-field", start, end));
+    }
+
+    // A field wider than a non-negative number is the whole of what remains of it, but the
+    // field of a negative number is all ones beyond its bits, so it must be built.
+    let width = (end - start) as u64;
+    let shifted = n >> start;
+    if !shifted.is_negative() && width >= shifted.bits() {
+        return Ok(new_integer(shifted));
+    }
+    if width > MAX_INTEGER_BITS {
+        return Err(too_large("bit-field", &args[2]));
+    }
+
+    let mask = (BigInt::one() << width) - BigInt::one();
+    Ok(new_integer(shifted & mask))
+}
@@ -0,0 +1,21 @@
+use crate::data::{Expr, Expression};
+use crate::eval::{Env, EvalResult};
+use crate::eval::primitives::{boolean, define};
+
+pub fn register(env: &Env) {
+    define(env, "boolean?", 1, Some(1), is_boolean);
+    define(env, "not", 1, Some(1), not);
+    define(env, "procedure?", 1, Some(1), is_procedure);
+}
+
+fn is_boolean(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(matches!(&*args[0], Expression::Boolean(_))))
+}
+
+fn not(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(!args[0].is_true()))
+}
+
+fn is_procedure(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(args[0].is_procedure()))
+}
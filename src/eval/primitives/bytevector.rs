@@ -0,0 +1,104 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use num::ToPrimitive;
+
+use crate::data::{Expr, Expression, Number};
+use crate::eval::{Env, EvalResult};
+use crate::eval::primitives::{boolean, define, index, integer, length, out_of_range, range, wrong_type};
+
+pub fn register(env: &Env) {
+    define(env, "bytevector?", 1, Some(1), is_bytevector);
+    define(env, "make-bytevector", 1, Some(2), make_bytevector);
+    define(env, "bytevector", 0, None, bytevector);
+    define(env, "bytevector-length", 1, Some(1), bytevector_length);
+    define(env, "bytevector-u8-ref", 2, Some(2), bytevector_u8_ref);
+    define(env, "bytevector-u8-set!", 3, Some(3), bytevector_u8_set);
+    define(env, "bytevector-copy", 1, Some(3), bytevector_copy);
+    define(env, "bytevector-append", 0, None, bytevector_append);
+}
+
+fn new_bytevector(bytes: Vec<u8>) -> Expr {
+    Rc::new(Expression::Bytevector(RefCell::new(bytes)))
+}
+
+fn bytes<'a>(name: &str, arg: &'a Expr) -> EvalResult<&'a RefCell<Vec<u8>>> {
+    match &**arg {
+        Expression::Bytevector(bytes) => Ok(bytes),
+        _ => Err(wrong_type(name, "bytevector", arg)),
+    }
+}
+
+fn byte(name: &str, arg: &Expr) -> EvalResult<u8> {
+    match &**arg {
+        Expression::Number(Number::Integer(n)) => n.to_u8().ok_or_else(|| wrong_type(name, "byte", arg)),
+        _ => Err(wrong_type(name, "byte", arg)),
+    }
+}
+
+fn is_bytevector(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(matches!(&*args[0], Expression::Bytevector(_))))
+}
+
+fn make_bytevector(args: &[Expr]) -> EvalResult<Expr> {
+    let length = length("make-bytevector", &args[0])?;
+    let fill = match args.get(1) {
+        Some(arg) => byte("make-bytevector", arg)?,
+        None => 0,
+    };
+
+    Ok(new_bytevector(vec![fill; length]))
+}
+
+fn bytevector(args: &[Expr]) -> EvalResult<Expr> {
+    let bytes = args.iter()
+        .map(|arg| byte("bytevector", arg))
+        .collect::<EvalResult<Vec<_>>>()?;
+
+    Ok(new_bytevector(bytes))
+}
+
+fn bytevector_length(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(integer(bytes("bytevector-length", &args[0])?.borrow().len()))
+}
+
+fn bytevector_u8_ref(args: &[Expr]) -> EvalResult<Expr> {
+    let bytes = bytes("bytevector-u8-ref", &args[0])?.borrow();
+    let k = index("bytevector-u8-ref", &args[1])?;
+
+    bytes.get(k)
+        .map(|b| integer(*b))
+        .ok_or_else(|| out_of_range("bytevector-u8-ref", k, bytes.len()))
+}
+
+fn bytevector_u8_set(args: &[Expr]) -> EvalResult<Expr> {
+    let mut bytes = bytes("bytevector-u8-set!", &args[0])?.borrow_mut();
+    let k = index("bytevector-u8-set!", &args[1])?;
+    let value = byte("bytevector-u8-set!", &args[2])?;
+    let length = bytes.len();
+
+    match bytes.get_mut(k) {
+        Some(slot) => {
+            *slot = value;
+            Ok(Rc::new(Expression::Unspecified))
+        },
+        None => Err(out_of_range("bytevector-u8-set!", k, length)),
+    }
+}
+
+fn bytevector_copy(args: &[Expr]) -> EvalResult<Expr> {
+    let bytes = bytes("bytevector-copy", &args[0])?.borrow();
+    let (start, end) = range("bytevector-copy", &args[1..], bytes.len())?;
+
+    Ok(new_bytevector(bytes[start..end].to_vec()))
+}
+
+fn bytevector_append(args: &[Expr]) -> EvalResult<Expr> {
+    let mut result = Vec::new();
+
+    for arg in args {
+        result.extend(bytes("bytevector-append", arg)?.borrow().iter());
+    }
+
+    Ok(new_bytevector(result))
+}
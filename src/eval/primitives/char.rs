@@ -0,0 +1,35 @@
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use num::ToPrimitive;
+
+use crate::data::{Expr, Expression, Number};
+use crate::eval::{Env, EvalResult};
+use crate::eval::primitives::{boolean, define, integer, wrong_type};
+
+pub fn register(env: &Env) {
+    define(env, "char?", 1, Some(1), is_char);
+    define(env, "char->integer", 1, Some(1), char_to_integer);
+    define(env, "integer->char", 1, Some(1), integer_to_char);
+}
+
+fn is_char(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(matches!(&*args[0], Expression::Char(_))))
+}
+
+fn char_to_integer(args: &[Expr]) -> EvalResult<Expr> {
+    match &*args[0] {
+        Expression::Char(ch) => Ok(integer(*ch as u32)),
+        _ => Err(wrong_type("char->integer", "character", &args[0])),
+    }
+}
+
+fn integer_to_char(args: &[Expr]) -> EvalResult<Expr> {
+    if let Expression::Number(Number::Integer(n)) = &*args[0] {
+        if let Some(ch) = n.to_u32().and_then(|n| char::try_from(n).ok()) {
+            return Ok(Rc::new(Expression::Char(ch)));
+        }
+    }
+
+    Err(wrong_type("integer->char", "unicode scalar value", &args[0]))
+}
@@ -0,0 +1,21 @@
+use std::rc::Rc;
+
+use crate::data::{Control, Expr, Expression, Values};
+use crate::eval::{Env, EvalResult};
+use crate::eval::primitives::define;
+
+pub fn register(env: &Env) {
+    let call_cc = Rc::new(Expression::Control(Control::CallWithCurrentContinuation));
+    env.define("call-with-current-continuation", call_cc.clone());
+    env.define("call/cc", call_cc);
+    env.define("dynamic-wind", Rc::new(Expression::Control(Control::DynamicWind)));
+    env.define("with-exception-handler", Rc::new(Expression::Control(Control::WithExceptionHandler)));
+    env.define("raise-continuable", Rc::new(Expression::Control(Control::RaiseContinuable)));
+    env.define("call-with-values", Rc::new(Expression::Control(Control::CallWithValues)));
+    env.define("load", Rc::new(Expression::Control(Control::Load)));
+    define(env, "values", 0, None, values);
+}
+
+fn values(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(Values::new(args.to_vec()).into_expr())
+}
@@ -0,0 +1,21 @@
+use crate::data::Expr;
+use crate::eval::{Env, EvalResult};
+use crate::eval::primitives::{boolean, define};
+
+pub fn register(env: &Env) {
+    define(env, "eq?", 2, Some(2), is_eq);
+    define(env, "eqv?", 2, Some(2), is_eqv);
+    define(env, "equal?", 2, Some(2), is_equal);
+}
+
+fn is_eq(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(args[0].eqv(&args[1])))
+}
+
+fn is_eqv(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(args[0].eqv(&args[1])))
+}
+
+fn is_equal(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(args[0].equal(&args[1])))
+}
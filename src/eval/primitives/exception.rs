@@ -0,0 +1,63 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::data::{ErrorObject, ErrorObjectKind, Expr, Expression};
+use crate::eval::{Env, EvalErrorKind, EvalResult};
+use crate::eval::primitives::{boolean, define, wrong_type};
+
+pub fn register(env: &Env) {
+    define(env, "raise", 1, Some(1), raise);
+    define(env, "error", 1, None, error);
+    define(env, "error-object?", 1, Some(1), is_error_object);
+    define(env, "error-object-message", 1, Some(1), error_object_message);
+    define(env, "error-object-irritants", 1, Some(1), error_object_irritants);
+    define(env, "read-error?", 1, Some(1), is_read_error);
+    define(env, "file-error?", 1, Some(1), is_file_error);
+}
+
+fn error_object<'a>(name: &str, arg: &'a Expr) -> EvalResult<&'a ErrorObject> {
+    match &**arg {
+        Expression::ErrorObject(error) => Ok(error),
+        _ => Err(wrong_type(name, "error object", arg)),
+    }
+}
+
+fn is_error_kind(arg: &Expr, kind: ErrorObjectKind) -> bool {
+    matches!(&**arg, Expression::ErrorObject(error) if error.kind == kind)
+}
+
+fn raise(args: &[Expr]) -> EvalResult<Expr> {
+    Err(EvalErrorKind::Raise(args[0].clone()).into())
+}
+
+fn error(args: &[Expr]) -> EvalResult<Expr> {
+    let message = match &*args[0] {
+        Expression::String(s) => s.borrow().clone(),
+        _ => return Err(wrong_type("error", "string", &args[0])),
+    };
+
+    let error = ErrorObject::new(message, args[1..].to_vec());
+    Err(EvalErrorKind::Raise(Rc::new(Expression::ErrorObject(Rc::new(error)))).into())
+}
+
+fn is_error_object(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(matches!(&*args[0], Expression::ErrorObject(_))))
+}
+
+fn error_object_message(args: &[Expr]) -> EvalResult<Expr> {
+    let error = error_object("error-object-message", &args[0])?;
+    Ok(Rc::new(Expression::String(RefCell::new(error.message.clone()))))
+}
+
+fn error_object_irritants(args: &[Expr]) -> EvalResult<Expr> {
+    let error = error_object("error-object-irritants", &args[0])?;
+    Ok(Expression::list(error.irritants.clone()))
+}
+
+fn is_read_error(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(is_error_kind(&args[0], ErrorObjectKind::Read)))
+}
+
+fn is_file_error(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(is_error_kind(&args[0], ErrorObjectKind::File)))
+}
@@ -0,0 +1,44 @@
+use std::rc::Rc;
+
+use crate::data::{Expr, Expression};
+use crate::eval::{Env, EvalResult};
+use crate::eval::primitives::{boolean, define, wrong_type};
+
+pub fn register(env: &Env) {
+    define(env, "pair?", 1, Some(1), is_pair);
+    define(env, "null?", 1, Some(1), is_null);
+    define(env, "cons", 2, Some(2), cons);
+    define(env, "car", 1, Some(1), car);
+    define(env, "cdr", 1, Some(1), cdr);
+    define(env, "list", 0, None, list);
+}
+
+fn is_pair(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(matches!(&*args[0], Expression::Pair(_, _))))
+}
+
+fn is_null(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(matches!(&*args[0], Expression::Nil)))
+}
+
+fn cons(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(Rc::new(Expression::Pair(args[0].clone(), args[1].clone())))
+}
+
+fn car(args: &[Expr]) -> EvalResult<Expr> {
+    match &*args[0] {
+        Expression::Pair(h, _) => Ok(h.clone()),
+        _ => Err(wrong_type("car", "pair", &args[0])),
+    }
+}
+
+fn cdr(args: &[Expr]) -> EvalResult<Expr> {
+    match &*args[0] {
+        Expression::Pair(_, t) => Ok(t.clone()),
+        _ => Err(wrong_type("cdr", "pair", &args[0])),
+    }
+}
+
+fn list(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(Expression::list(args.to_vec()))
+}
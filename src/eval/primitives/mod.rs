@@ -0,0 +1,105 @@
+use std::rc::Rc;
+
+use num::{BigInt, ToPrimitive};
+
+use crate::data::{Expr, Expression, Number, Primitive};
+use crate::eval::{Env, EvalError, EvalErrorKind, EvalResult};
+
+mod bitwise;
+mod boolean;
+mod bytevector;
+mod char;
+mod control;
+mod equivalence;
+mod exception;
+mod list;
+mod numeric;
+mod string;
+mod vector;
+
+pub fn register(env: &Env) {
+    bitwise::register(env);
+    boolean::register(env);
+    bytevector::register(env);
+    char::register(env);
+    control::register(env);
+    equivalence::register(env);
+    exception::register(env);
+    list::register(env);
+    numeric::register(env);
+    string::register(env);
+    vector::register(env);
+}
+
+/// The size in bits of the largest exact integer a primitive will build, so that huge operands
+/// raise an error instead of exhausting memory.
+const MAX_INTEGER_BITS: u64 = 1 << 24;
+
+fn define(env: &Env, name: &'static str, min_args: usize, max_args: Option<usize>, func: fn(&[Expr]) -> EvalResult<Expr>) {
+    let primitive = Primitive { name, min_args, max_args, func: Box::new(func) };
+    env.define(name, Rc::new(Expression::Primitive(Rc::new(primitive))));
+}
+
+fn boolean(value: bool) -> Expr {
+    Rc::new(Expression::Boolean(value))
+}
+
+fn integer<T: Into<BigInt>>(value: T) -> Expr {
+    Rc::new(Expression::Number(Number::Integer(value.into())))
+}
+
+fn index(name: &str, arg: &Expr) -> EvalResult<usize> {
+    match &**arg {
+        Expression::Number(Number::Integer(n)) => n.to_usize().ok_or_else(|| wrong_type(name, "index", arg)),
+        _ => Err(wrong_type(name, "index", arg)),
+    }
+}
+
+/// The most elements a primitive will allocate for a new vector, bytevector or string.
+const MAX_LENGTH: usize = 1 << 24;
+
+/// Reads the length of a new vector, bytevector or string, which must not exceed `MAX_LENGTH`.
+fn length(name: &str, arg: &Expr) -> EvalResult<usize> {
+    let length = index(name, arg)?;
+    if length > MAX_LENGTH {
+        return Err(out_of_range(name, length, MAX_LENGTH));
+    }
+
+    Ok(length)
+}
+
+fn range(name: &str, bounds: &[Expr], length: usize) -> EvalResult<(usize, usize)> {
+    let start = match bounds.first() {
+        Some(arg) => index(name, arg)?,
+        None => 0,
+    };
+    let end = match bounds.get(1) {
+        Some(arg) => index(name, arg)?,
+        None => length,
+    };
+
+    if end > length {
+        return Err(out_of_range(name, end, length));
+    }
+    if start > end {
+        return Err(out_of_range(name, start, end));
+    }
+
+    Ok((start, end))
+}
+
+fn wrong_type(name: &str, expected: &str, arg: &Expr) -> EvalError {
+    EvalErrorKind::WrongType { procedure: name.to_string(), expected: expected.to_string(), actual: arg.clone() }.into()
+}
+
+fn too_large(name: &str, arg: &Expr) -> EvalError {
+    wrong_type(name, &format!("operand giving a result of at most {} bits", MAX_INTEGER_BITS), arg)
+}
+
+fn out_of_range(name: &str, index: usize, length: usize) -> EvalError {
+    EvalErrorKind::IndexOutOfRange { procedure: name.to_string(), index, length }.into()
+}
+
+fn division_by_zero(name: &str) -> EvalError {
+    EvalErrorKind::DivisionByZero(name.to_string()).into()
+}
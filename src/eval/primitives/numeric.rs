@@ -0,0 +1,492 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use num::{BigRational, Integer, ToPrimitive};
+
+use crate::data::{Expr, Expression, Number, Values};
+use crate::eval::{Env, EvalResult};
+use crate::eval::primitives::{boolean, define, division_by_zero, too_large, wrong_type, MAX_INTEGER_BITS};
+use crate::read::read_number;
+
+pub fn register(env: &Env) {
+    define(env, "number?", 1, Some(1), is_number);
+    define(env, "complex?", 1, Some(1), is_number);
+    define(env, "real?", 1, Some(1), is_real);
+    define(env, "rational?", 1, Some(1), is_rational);
+    define(env, "integer?", 1, Some(1), is_integer);
+    define(env, "exact?", 1, Some(1), is_exact);
+    define(env, "inexact?", 1, Some(1), is_inexact);
+    define(env, "=", 1, None, num_eq);
+    define(env, "<", 1, None, num_lt);
+    define(env, ">", 1, None, num_gt);
+    define(env, "<=", 1, None, num_le);
+    define(env, ">=", 1, None, num_ge);
+    define(env, "+", 0, None, add);
+    define(env, "-", 1, None, sub);
+    define(env, "*", 0, None, mul);
+    define(env, "/", 1, None, div);
+    define(env, "quotient", 2, Some(2), quotient);
+    define(env, "remainder", 2, Some(2), remainder);
+    define(env, "modulo", 2, Some(2), modulo);
+    define(env, "floor/", 2, Some(2), floor_div);
+    define(env, "floor-quotient", 2, Some(2), floor_quotient);
+    define(env, "floor-remainder", 2, Some(2), floor_remainder);
+    define(env, "truncate/", 2, Some(2), truncate_div);
+    define(env, "truncate-quotient", 2, Some(2), truncate_quotient);
+    define(env, "truncate-remainder", 2, Some(2), truncate_remainder);
+    define(env, "gcd", 0, None, gcd);
+    define(env, "lcm", 0, None, lcm);
+    define(env, "abs", 1, Some(1), abs);
+    define(env, "min", 1, None, min);
+    define(env, "max", 1, None, max);
+    define(env, "square", 1, Some(1), square);
+    define(env, "expt", 2, Some(2), expt);
+    define(env, "exact-integer-sqrt", 1, Some(1), exact_integer_sqrt);
+    define(env, "zero?", 1, Some(1), is_zero);
+    define(env, "positive?", 1, Some(1), is_positive);
+    define(env, "negative?", 1, Some(1), is_negative);
+    define(env, "odd?", 1, Some(1), is_odd);
+    define(env, "even?", 1, Some(1), is_even);
+    define(env, "exact-integer?", 1, Some(1), is_exact_integer);
+    define(env, "floor", 1, Some(1), floor);
+    define(env, "ceiling", 1, Some(1), ceiling);
+    define(env, "round", 1, Some(1), round);
+    define(env, "truncate", 1, Some(1), truncate);
+    define(env, "exact", 1, Some(1), exact);
+    define(env, "inexact", 1, Some(1), inexact);
+    define(env, "inexact->exact", 1, Some(1), exact);
+    define(env, "exact->inexact", 1, Some(1), inexact);
+    define(env, "number->string", 1, Some(2), number_to_string);
+    define(env, "string->number", 1, Some(2), string_to_number);
+    define(env, "make-rectangular", 2, Some(2), make_rectangular);
+    define(env, "make-polar", 2, Some(2), make_polar);
+    define(env, "real-part", 1, Some(1), real_part);
+    define(env, "imag-part", 1, Some(1), imag_part);
+    define(env, "magnitude", 1, Some(1), magnitude);
+    define(env, "angle", 1, Some(1), angle);
+    define(env, "exp", 1, Some(1), exp);
+    define(env, "log", 1, Some(2), log);
+    define(env, "sqrt", 1, Some(1), sqrt);
+    define(env, "sin", 1, Some(1), sin);
+    define(env, "cos", 1, Some(1), cos);
+    define(env, "tan", 1, Some(1), tan);
+    define(env, "asin", 1, Some(1), asin);
+    define(env, "acos", 1, Some(1), acos);
+    define(env, "atan", 1, Some(2), atan);
+}
+
+fn new_number(value: Number) -> Expr {
+    Rc::new(Expression::Number(value))
+}
+
+fn number<'a>(name: &str, arg: &'a Expr) -> EvalResult<&'a Number> {
+    match &**arg {
+        Expression::Number(n) => Ok(n),
+        _ => Err(wrong_type(name, "number", arg)),
+    }
+}
+
+fn real<'a>(name: &str, arg: &'a Expr) -> EvalResult<&'a Number> {
+    match &**arg {
+        Expression::Number(n) if n.is_real() => Ok(n),
+        _ => Err(wrong_type(name, "real number", arg)),
+    }
+}
+
+fn integral<'a>(name: &str, arg: &'a Expr) -> EvalResult<&'a Number> {
+    match &**arg {
+        Expression::Number(n) if n.is_integer() => Ok(n),
+        _ => Err(wrong_type(name, "integer", arg)),
+    }
+}
+
+fn numbers<'a>(name: &str, args: &'a [Expr]) -> EvalResult<Vec<&'a Number>> {
+    args.iter().map(|arg| number(name, arg)).collect()
+}
+
+fn is_number(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(matches!(&*args[0], Expression::Number(_))))
+}
+
+fn is_real(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(matches!(&*args[0], Expression::Number(n) if n.is_real())))
+}
+
+fn is_rational(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(matches!(&*args[0], Expression::Number(n) if n.is_rational())))
+}
+
+fn is_integer(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(matches!(&*args[0], Expression::Number(n) if n.is_integer())))
+}
+
+fn is_exact(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(number("exact?", &args[0])?.is_exact()))
+}
+
+fn is_inexact(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(!number("inexact?", &args[0])?.is_exact()))
+}
+
+fn compare(name: &str, args: &[Expr], test: fn(&Number, &Number) -> bool) -> EvalResult<Expr> {
+    let numbers = args.iter()
+        .map(|arg| if name == "=" { number(name, arg) } else { real(name, arg) })
+        .collect::<EvalResult<Vec<_>>>()?;
+
+    Ok(boolean(numbers.windows(2).all(|pair| test(pair[0], pair[1]))))
+}
+
+fn num_eq(args: &[Expr]) -> EvalResult<Expr> {
+    compare("=", args, |a, b| a == b)
+}
+
+fn num_lt(args: &[Expr]) -> EvalResult<Expr> {
+    compare("<", args, |a, b| a < b)
+}
+
+fn num_gt(args: &[Expr]) -> EvalResult<Expr> {
+    compare(">", args, |a, b| a > b)
+}
+
+fn num_le(args: &[Expr]) -> EvalResult<Expr> {
+    compare("<=", args, |a, b| a <= b)
+}
+
+fn num_ge(args: &[Expr]) -> EvalResult<Expr> {
+    compare(">=", args, |a, b| a >= b)
+}
+
+fn add(args: &[Expr]) -> EvalResult<Expr> {
+    let result = numbers("+", args)?.iter()
+        .fold(Number::Integer(0.into()), |acc, n| acc.add(n));
+
+    Ok(new_number(result))
+}
+
+fn sub(args: &[Expr]) -> EvalResult<Expr> {
+    let numbers = numbers("-", args)?;
+
+    let result = match numbers.split_first() {
+        Some((first, [])) => first.neg(),
+        Some((first, rest)) => rest.iter().fold((*first).clone(), |acc, n| acc.sub(n)),
+        None => unreachable!(),
+    };
+
+    Ok(new_number(result))
+}
+
+fn mul(args: &[Expr]) -> EvalResult<Expr> {
+    let result = numbers("*", args)?.iter()
+        .fold(Number::Integer(1.into()), |acc, n| acc.mul(n));
+
+    Ok(new_number(result))
+}
+
+fn div(args: &[Expr]) -> EvalResult<Expr> {
+    let numbers = numbers("/", args)?;
+
+    let (mut result, rest) = match numbers.split_first() {
+        Some((_, [])) => (Number::Integer(1.into()), &numbers[..]),
+        Some((first, rest)) => ((*first).clone(), rest),
+        None => unreachable!(),
+    };
+
+    for n in rest {
+        result = result.div(n).ok_or_else(|| division_by_zero("/"))?;
+    }
+
+    Ok(new_number(result))
+}
+
+fn divide(name: &str, args: &[Expr], floor: bool) -> EvalResult<(Number, Number)> {
+    let n = integral(name, &args[0])?;
+    let d = integral(name, &args[1])?;
+    let result = if floor { n.floor_div(d) } else { n.truncate_div(d) };
+
+    result.ok_or_else(|| division_by_zero(name))
+}
+
+fn quotient(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(divide("quotient", args, false)?.0))
+}
+
+fn remainder(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(divide("remainder", args, false)?.1))
+}
+
+fn modulo(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(divide("modulo", args, true)?.1))
+}
+
+fn floor_div(args: &[Expr]) -> EvalResult<Expr> {
+    let (q, r) = divide("floor/", args, true)?;
+    Ok(Values::new(vec![new_number(q), new_number(r)]).into_expr())
+}
+
+fn floor_quotient(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(divide("floor-quotient", args, true)?.0))
+}
+
+fn floor_remainder(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(divide("floor-remainder", args, true)?.1))
+}
+
+fn truncate_div(args: &[Expr]) -> EvalResult<Expr> {
+    let (q, r) = divide("truncate/", args, false)?;
+    Ok(Values::new(vec![new_number(q), new_number(r)]).into_expr())
+}
+
+fn truncate_quotient(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(divide("truncate-quotient", args, false)?.0))
+}
+
+fn truncate_remainder(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(divide("truncate-remainder", args, false)?.1))
+}
+
+fn gcd(args: &[Expr]) -> EvalResult<Expr> {
+    let mut result = Number::Integer(0.into());
+
+    for arg in args {
+        result = result.gcd(integral("gcd", arg)?).unwrap();
+    }
+
+    Ok(new_number(result))
+}
+
+fn lcm(args: &[Expr]) -> EvalResult<Expr> {
+    let mut result = Number::Integer(1.into());
+
+    for arg in args {
+        result = result.lcm(integral("lcm", arg)?).unwrap();
+    }
+
+    Ok(new_number(result))
+}
+
+fn abs(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(real("abs", &args[0])?.abs()))
+}
+
+fn min(args: &[Expr]) -> EvalResult<Expr> {
+    let mut result = real("min", &args[0])?.clone();
+
+    for arg in &args[1..] {
+        result = result.min(real("min", arg)?);
+    }
+
+    Ok(new_number(result))
+}
+
+fn max(args: &[Expr]) -> EvalResult<Expr> {
+    let mut result = real("max", &args[0])?.clone();
+
+    for arg in &args[1..] {
+        result = result.max(real("max", arg)?);
+    }
+
+    Ok(new_number(result))
+}
+
+fn square(args: &[Expr]) -> EvalResult<Expr> {
+    let z = number("square", &args[0])?;
+    Ok(new_number(z.mul(z)))
+}
+
+fn expt(args: &[Expr]) -> EvalResult<Expr> {
+    let base = number("expt", &args[0])?;
+    let power = number("expt", &args[1])?;
+
+    fn rational_bits(r: &BigRational) -> u64 {
+        r.numer().bits().max(r.denom().bits())
+    }
+
+    // An exact power of an exact base other than 0, 1 or -1 grows by the base's size in bits
+    // for each step of the power. The magnitude of an exact complex base can take one more bit
+    // than its parts.
+    let base_bits = match base {
+        Number::Integer(i) => Some(i.bits()),
+        Number::Rational(r) => Some(rational_bits(r)),
+        Number::ExactComplex(c) => Some(rational_bits(&c.re).max(rational_bits(&c.im)) + 1),
+        _ => None,
+    };
+    if let (Some(bits), Number::Integer(n)) = (base_bits, power) {
+        let result_bits = n.magnitude().to_u64().and_then(|n| n.checked_mul(bits));
+        if bits > 1 && result_bits.is_none_or(|bits| bits > MAX_INTEGER_BITS) {
+            return Err(too_large("expt", &args[1]));
+        }
+    }
+
+    base.expt(power)
+        .map(new_number)
+        .ok_or_else(|| division_by_zero("expt"))
+}
+
+fn exact_integer_sqrt(args: &[Expr]) -> EvalResult<Expr> {
+    let (root, rest) = number("exact-integer-sqrt", &args[0])?
+        .exact_integer_sqrt()
+        .ok_or_else(|| wrong_type("exact-integer-sqrt", "exact non-negative integer", &args[0]))?;
+
+    Ok(Values::new(vec![new_number(root), new_number(rest)]).into_expr())
+}
+
+fn is_zero(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(number("zero?", &args[0])?.is_zero()))
+}
+
+fn is_positive(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(*real("positive?", &args[0])? > Number::Integer(0.into())))
+}
+
+fn is_negative(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(real("negative?", &args[0])?.is_negative()))
+}
+
+fn is_odd(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(integral("odd?", &args[0])?.to_integer().unwrap().is_odd()))
+}
+
+fn is_even(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(integral("even?", &args[0])?.to_integer().unwrap().is_even()))
+}
+
+fn is_exact_integer(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(matches!(&*args[0], Expression::Number(Number::Integer(_)))))
+}
+
+fn floor(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(real("floor", &args[0])?.floor()))
+}
+
+fn ceiling(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(real("ceiling", &args[0])?.ceiling()))
+}
+
+fn round(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(real("round", &args[0])?.round()))
+}
+
+fn truncate(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(real("truncate", &args[0])?.truncate()))
+}
+
+fn exact(args: &[Expr]) -> EvalResult<Expr> {
+    number("exact", &args[0])?
+        .to_exact()
+        .map(new_number)
+        .ok_or_else(|| wrong_type("exact", "number with an exact representation", &args[0]))
+}
+
+fn inexact(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(number("inexact", &args[0])?.to_inexact()))
+}
+
+fn radix(name: &str, args: &[Expr]) -> EvalResult<u32> {
+    let arg = match args.get(1) {
+        Some(arg) => arg,
+        None => return Ok(10),
+    };
+
+    match &**arg {
+        Expression::Number(Number::Integer(n)) => match n.to_u32() {
+            Some(radix @ (2 | 8 | 10 | 16)) => Ok(radix),
+            _ => Err(wrong_type(name, "radix 2, 8, 10 or 16", arg)),
+        },
+        _ => Err(wrong_type(name, "radix 2, 8, 10 or 16", arg)),
+    }
+}
+
+fn number_to_string(args: &[Expr]) -> EvalResult<Expr> {
+    let radix = radix("number->string", args)?;
+    let text = number("number->string", &args[0])?
+        .to_string_radix(radix)
+        .ok_or_else(|| wrong_type("number->string", &format!("number writable in radix {}", radix), &args[0]))?;
+
+    Ok(Rc::new(Expression::String(RefCell::new(text))))
+}
+
+fn string_to_number(args: &[Expr]) -> EvalResult<Expr> {
+    let radix = radix("string->number", args)?;
+
+    match &*args[0] {
+        Expression::String(s) => Ok(read_number(&s.borrow(), radix).map_or_else(|| boolean(false), new_number)),
+        _ => Err(wrong_type("string->number", "string", &args[0])),
+    }
+}
+
+fn make_rectangular(args: &[Expr]) -> EvalResult<Expr> {
+    let real_part = real("make-rectangular", &args[0])?;
+    let imag_part = real("make-rectangular", &args[1])?;
+
+    Ok(new_number(Number::rectangular(real_part, imag_part)))
+}
+
+fn make_polar(args: &[Expr]) -> EvalResult<Expr> {
+    let magnitude = real("make-polar", &args[0])?;
+    let angle = real("make-polar", &args[1])?;
+
+    Ok(new_number(Number::polar(magnitude, angle)))
+}
+
+fn real_part(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(number("real-part", &args[0])?.real_part()))
+}
+
+fn imag_part(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(number("imag-part", &args[0])?.imag_part()))
+}
+
+fn magnitude(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(number("magnitude", &args[0])?.magnitude()))
+}
+
+fn angle(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(number("angle", &args[0])?.angle()))
+}
+
+fn exp(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(number("exp", &args[0])?.exp()))
+}
+
+fn log(args: &[Expr]) -> EvalResult<Expr> {
+    let z = number("log", &args[0])?.log();
+
+    match args.get(1) {
+        Some(base) => {
+            let base = number("log", base)?.log();
+            z.div(&base).map(new_number).ok_or_else(|| division_by_zero("log"))
+        },
+        None => Ok(new_number(z)),
+    }
+}
+
+fn sqrt(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(number("sqrt", &args[0])?.sqrt()))
+}
+
+fn sin(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(number("sin", &args[0])?.sin()))
+}
+
+fn cos(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(number("cos", &args[0])?.cos()))
+}
+
+fn tan(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(number("tan", &args[0])?.tan()))
+}
+
+fn asin(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(number("asin", &args[0])?.asin()))
+}
+
+fn acos(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_number(number("acos", &args[0])?.acos()))
+}
+
+fn atan(args: &[Expr]) -> EvalResult<Expr> {
+    match args.get(1) {
+        Some(x) => Ok(new_number(real("atan", &args[0])?.atan2(real("atan", x)?))),
+        None => Ok(new_number(number("atan", &args[0])?.atan())),
+    }
+}
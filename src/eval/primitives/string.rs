@@ -0,0 +1,126 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::data::{Expr, Expression};
+use crate::eval::{Env, EvalResult};
+use crate::eval::primitives::{boolean, define, index, integer, length, out_of_range, range, wrong_type};
+
+pub fn register(env: &Env) {
+    define(env, "string?", 1, Some(1), is_string);
+    define(env, "make-string", 1, Some(2), make_string);
+    define(env, "string", 0, None, string);
+    define(env, "string-length", 1, Some(1), string_length);
+    define(env, "string-ref", 2, Some(2), string_ref);
+    define(env, "string-set!", 3, Some(3), string_set);
+    define(env, "substring", 2, Some(3), substring);
+    define(env, "string-append", 0, None, string_append);
+    define(env, "string-copy", 1, Some(3), string_copy);
+    define(env, "string->symbol", 1, Some(1), string_to_symbol);
+    define(env, "symbol->string", 1, Some(1), symbol_to_string);
+}
+
+fn new_string(value: String) -> Expr {
+    Rc::new(Expression::String(RefCell::new(value)))
+}
+
+fn text(name: &str, arg: &Expr) -> EvalResult<String> {
+    match &**arg {
+        Expression::String(s) => Ok(s.borrow().clone()),
+        _ => Err(wrong_type(name, "string", arg)),
+    }
+}
+
+fn character(name: &str, arg: &Expr) -> EvalResult<char> {
+    match &**arg {
+        Expression::Char(ch) => Ok(*ch),
+        _ => Err(wrong_type(name, "character", arg)),
+    }
+}
+
+fn is_string(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(matches!(&*args[0], Expression::String(_))))
+}
+
+fn make_string(args: &[Expr]) -> EvalResult<Expr> {
+    let length = length("make-string", &args[0])?;
+    let fill = match args.get(1) {
+        Some(arg) => character("make-string", arg)?,
+        None => ' ',
+    };
+
+    Ok(new_string(std::iter::repeat_n(fill, length).collect()))
+}
+
+fn string(args: &[Expr]) -> EvalResult<Expr> {
+    let chars = args.iter()
+        .map(|arg| character("string", arg))
+        .collect::<EvalResult<String>>()?;
+
+    Ok(new_string(chars))
+}
+
+fn string_length(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(integer(text("string-length", &args[0])?.chars().count()))
+}
+
+fn string_ref(args: &[Expr]) -> EvalResult<Expr> {
+    let s = text("string-ref", &args[0])?;
+    let k = index("string-ref", &args[1])?;
+
+    s.chars().nth(k)
+        .map(|ch| Rc::new(Expression::Char(ch)))
+        .ok_or_else(|| out_of_range("string-ref", k, s.chars().count()))
+}
+
+fn string_set(args: &[Expr]) -> EvalResult<Expr> {
+    let k = index("string-set!", &args[1])?;
+    let ch = character("string-set!", &args[2])?;
+
+    match &*args[0] {
+        Expression::String(s) => {
+            let mut chars = s.borrow().chars().collect::<Vec<_>>();
+            if k >= chars.len() {
+                return Err(out_of_range("string-set!", k, chars.len()));
+            }
+
+            chars[k] = ch;
+            *s.borrow_mut() = chars.into_iter().collect();
+            Ok(Rc::new(Expression::Unspecified))
+        },
+        _ => Err(wrong_type("string-set!", "string", &args[0])),
+    }
+}
+
+fn substring(args: &[Expr]) -> EvalResult<Expr> {
+    copy("substring", args)
+}
+
+fn string_append(args: &[Expr]) -> EvalResult<Expr> {
+    let text = args.iter()
+        .map(|arg| text("string-append", arg))
+        .collect::<EvalResult<String>>()?;
+
+    Ok(new_string(text))
+}
+
+fn string_copy(args: &[Expr]) -> EvalResult<Expr> {
+    copy("string-copy", args)
+}
+
+fn copy(name: &str, args: &[Expr]) -> EvalResult<Expr> {
+    let s = text(name, &args[0])?;
+    let (start, end) = range(name, &args[1..], s.chars().count())?;
+
+    Ok(new_string(s.chars().skip(start).take(end - start).collect()))
+}
+
+fn string_to_symbol(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(Rc::new(Expression::Symbol(text("string->symbol", &args[0])?)))
+}
+
+fn symbol_to_string(args: &[Expr]) -> EvalResult<Expr> {
+    match &*args[0] {
+        Expression::Symbol(name) => Ok(new_string(name.clone())),
+        _ => Err(wrong_type("symbol->string", "symbol", &args[0])),
+    }
+}
@@ -0,0 +1,115 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::data::{Expr, Expression};
+use crate::eval::{Env, EvalResult};
+use crate::eval::primitives::{boolean, define, index, integer, length, out_of_range, range, wrong_type};
+
+pub fn register(env: &Env) {
+    define(env, "vector?", 1, Some(1), is_vector);
+    define(env, "make-vector", 1, Some(2), make_vector);
+    define(env, "vector", 0, None, vector);
+    define(env, "vector-length", 1, Some(1), vector_length);
+    define(env, "vector-ref", 2, Some(2), vector_ref);
+    define(env, "vector-set!", 3, Some(3), vector_set);
+    define(env, "vector->list", 1, Some(3), vector_to_list);
+    define(env, "list->vector", 1, Some(1), list_to_vector);
+    define(env, "vector-fill!", 2, Some(4), vector_fill);
+    define(env, "vector-copy", 1, Some(3), vector_copy);
+    define(env, "vector-append", 0, None, vector_append);
+}
+
+fn new_vector(items: Vec<Expr>) -> Expr {
+    Rc::new(Expression::Vector(RefCell::new(items)))
+}
+
+fn items<'a>(name: &str, arg: &'a Expr) -> EvalResult<&'a RefCell<Vec<Expr>>> {
+    match &**arg {
+        Expression::Vector(items) => Ok(items),
+        _ => Err(wrong_type(name, "vector", arg)),
+    }
+}
+
+fn is_vector(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(boolean(matches!(&*args[0], Expression::Vector(_))))
+}
+
+fn make_vector(args: &[Expr]) -> EvalResult<Expr> {
+    let length = length("make-vector", &args[0])?;
+    let fill = args.get(1).cloned().unwrap_or_else(|| Rc::new(Expression::Unspecified));
+
+    Ok(new_vector(vec![fill; length]))
+}
+
+fn vector(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(new_vector(args.to_vec()))
+}
+
+fn vector_length(args: &[Expr]) -> EvalResult<Expr> {
+    Ok(integer(items("vector-length", &args[0])?.borrow().len()))
+}
+
+fn vector_ref(args: &[Expr]) -> EvalResult<Expr> {
+    let items = items("vector-ref", &args[0])?.borrow();
+    let k = index("vector-ref", &args[1])?;
+
+    items.get(k)
+        .cloned()
+        .ok_or_else(|| out_of_range("vector-ref", k, items.len()))
+}
+
+fn vector_set(args: &[Expr]) -> EvalResult<Expr> {
+    let mut items = items("vector-set!", &args[0])?.borrow_mut();
+    let k = index("vector-set!", &args[1])?;
+    let length = items.len();
+
+    match items.get_mut(k) {
+        Some(slot) => {
+            *slot = args[2].clone();
+            Ok(Rc::new(Expression::Unspecified))
+        },
+        None => Err(out_of_range("vector-set!", k, length)),
+    }
+}
+
+fn vector_to_list(args: &[Expr]) -> EvalResult<Expr> {
+    let items = items("vector->list", &args[0])?.borrow();
+    let (start, end) = range("vector->list", &args[1..], items.len())?;
+
+    Ok(Expression::list(items[start..end].to_vec()))
+}
+
+fn list_to_vector(args: &[Expr]) -> EvalResult<Expr> {
+    match args[0].list_to_vec() {
+        Some(items) => Ok(new_vector(items)),
+        None => Err(wrong_type("list->vector", "list", &args[0])),
+    }
+}
+
+fn vector_fill(args: &[Expr]) -> EvalResult<Expr> {
+    let mut items = items("vector-fill!", &args[0])?.borrow_mut();
+    let (start, end) = range("vector-fill!", &args[2..], items.len())?;
+
+    for slot in &mut items[start..end] {
+        *slot = args[1].clone();
+    }
+
+    Ok(Rc::new(Expression::Unspecified))
+}
+
+fn vector_copy(args: &[Expr]) -> EvalResult<Expr> {
+    let items = items("vector-copy", &args[0])?.borrow();
+    let (start, end) = range("vector-copy", &args[1..], items.len())?;
+
+    Ok(new_vector(items[start..end].to_vec()))
+}
+
+fn vector_append(args: &[Expr]) -> EvalResult<Expr> {
+    let mut result = Vec::new();
+
+    for arg in args {
+        result.extend(items("vector-append", arg)?.borrow().iter().cloned());
+    }
+
+    Ok(new_vector(result))
+}
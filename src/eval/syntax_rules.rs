@@ -0,0 +1,324 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::data::{Alias, Expr, Expression};
+use crate::eval::expander::same_identifier;
+use crate::eval::expr_evaluator::{args, bad_syntax};
+use crate::eval::{EvalResult, Scope};
+
+/// A `syntax-rules` transformer: a list of pattern and template pairs, tried in order.
+pub struct SyntaxRules {
+    ellipsis: Option<Expr>,
+    literals: Vec<Expr>,
+    rules: Vec<(Expr, Expr)>,
+    scope: Rc<Scope>,
+}
+
+/// What a pattern variable matched: a single form, or one match per repetition of an ellipsis.
+#[derive(Clone)]
+enum Match {
+    One(Expr),
+    Many(Vec<Bindings>),
+}
+
+type Bindings = Vec<(Expr, Match)>;
+
+impl SyntaxRules {
+    /// Parses the operands of a `syntax-rules` form defined in `scope`.
+    pub (crate) fn new(spec: &Expr, scope: &Rc<Scope>) -> EvalResult<SyntaxRules> {
+        let items = args(spec, "syntax-rules")?;
+        let (ellipsis, rest) = match items.split_first() {
+            Some((ellipsis, rest)) if ellipsis.is_identifier() => (Some(ellipsis.clone()), rest),
+            _ => (None, items.as_slice()),
+        };
+
+        let (literals, rules) = match rest.split_first() {
+            Some((literals, rules)) => (args(literals, "syntax-rules")?, rules),
+            None => return Err(bad_syntax("syntax-rules", spec)),
+        };
+        if !literals.iter().all(|l| l.is_identifier()) {
+            return Err(bad_syntax("syntax-rules", spec));
+        }
+
+        let rules = rules.iter()
+            .map(|rule| match rule.list_to_vec().as_deref() {
+                Some([pattern, template]) if matches!(**pattern, Expression::Pair(..)) => {
+                    Ok((pattern.clone(), template.clone()))
+                },
+                _ => Err(bad_syntax("syntax-rules", rule)),
+            })
+            .collect::<EvalResult<Vec<_>>>()?;
+
+        Ok(SyntaxRules { ellipsis, literals, rules, scope: scope.clone() })
+    }
+
+    /// Rewrites a use of the macro named `name` with the first rule whose pattern matches it.
+    /// `is_literal` decides whether an identifier in the form means the same as a literal
+    /// identifier in the patterns.
+    pub (crate) fn expand(&self, name: &str, form: &Expr, is_literal: &dyn Fn(&Expr, &Expr) -> bool) -> EvalResult<Expr> {
+        for (pattern, template) in &self.rules {
+            let mut bindings = Vec::new();
+
+            // The keyword position is ignored, since it may be an alias of the macro's name.
+            if let (Expression::Pair(_, pattern), Expression::Pair(_, input)) = (&**pattern, &**form) {
+                if self.matches(pattern, input, &mut bindings, is_literal) {
+                    let renames = RefCell::new(Vec::new());
+                    return self.transcribe(template, &bindings, &renames, false);
+                }
+            }
+        }
+
+        Err(bad_syntax(name, form))
+    }
+
+    /// The scope the macro was defined in.
+    pub (crate) fn scope(&self) -> &Rc<Scope> {
+        &self.scope
+    }
+
+    fn is_ellipsis(&self, expr: &Expr) -> bool {
+        match &self.ellipsis {
+            Some(ellipsis) => same_identifier(ellipsis, expr),
+            None => expr.identifier_name() == Some("..."),
+        }
+    }
+
+    fn matches(&self, pattern: &Expr, input: &Expr, bindings: &mut Bindings, is_literal: &dyn Fn(&Expr, &Expr) -> bool) -> bool {
+        use Expression::*;
+
+        match &**pattern {
+            Symbol(_) | Alias(_) => {
+                if self.literals.iter().any(|l| same_identifier(l, pattern)) {
+                    input.is_identifier() && is_literal(pattern, input)
+                } else {
+                    if pattern.identifier_name() != Some("_") {
+                        bindings.push((pattern.clone(), Match::One(input.clone())));
+                    }
+                    true
+                }
+            },
+            Pair(..) => {
+                let (patterns, tail) = split_list(pattern);
+                let (inputs, input_tail) = split_list(input);
+                self.matches_sequence(&patterns, &tail, &inputs, &input_tail, bindings, is_literal)
+            },
+            Vector(patterns) => match &**input {
+                Vector(inputs) => {
+                    let (patterns, inputs) = (patterns.borrow().clone(), inputs.borrow().clone());
+                    let nil = Rc::new(Nil);
+                    self.matches_sequence(&patterns, &nil, &inputs, &nil, bindings, is_literal)
+                },
+                _ => false,
+            },
+            _ => pattern.equal(input),
+        }
+    }
+
+    /// Matches the elements of a list or vector pattern, at most one of which may be followed by
+    /// an ellipsis, and then the final tail of the pattern against what remains of the input.
+    fn matches_sequence(
+        &self,
+        patterns: &[Expr],
+        tail: &Expr,
+        inputs: &[Expr],
+        input_tail: &Expr,
+        bindings: &mut Bindings,
+        is_literal: &dyn Fn(&Expr, &Expr) -> bool,
+    ) -> bool {
+        let ellipsis = patterns.iter().position(|p| self.is_ellipsis(p));
+
+        let (before, repeated, after) = match ellipsis {
+            Some(0) | None => (patterns, None, &[][..]),
+            Some(i) => (&patterns[..i - 1], Some(&patterns[i - 1]), &patterns[i + 1..]),
+        };
+
+        let fixed = before.len() + after.len();
+        let exact = repeated.is_none() && matches!(**tail, Expression::Nil);
+        if inputs.len() < fixed || (exact && inputs.len() != fixed) {
+            return false;
+        }
+
+        for (pattern, input) in before.iter().zip(inputs) {
+            if !self.matches(pattern, input, bindings, is_literal) {
+                return false;
+            }
+        }
+
+        let rest = match repeated {
+            Some(repeated) => {
+                let end = inputs.len() - after.len();
+                let mut matches = Vec::new();
+
+                for input in &inputs[before.len()..end] {
+                    let mut inner = Vec::new();
+                    if !self.matches(repeated, input, &mut inner, is_literal) {
+                        return false;
+                    }
+                    matches.push(inner);
+                }
+
+                for var in self.pattern_variables(repeated) {
+                    let each = matches.iter()
+                        .map(|m| m.iter().filter(|(v, _)| same_identifier(v, &var)).cloned().collect())
+                        .collect();
+                    bindings.push((var, Match::Many(each)));
+                }
+
+                for (pattern, input) in after.iter().zip(&inputs[end..]) {
+                    if !self.matches(pattern, input, bindings, is_literal) {
+                        return false;
+                    }
+                }
+
+                input_tail.clone()
+            },
+            None => rebuild_list(&inputs[before.len()..], input_tail.clone()),
+        };
+
+        self.matches(tail, &rest, bindings, is_literal)
+    }
+
+    fn pattern_variables(&self, pattern: &Expr) -> Vec<Expr> {
+        use Expression::*;
+
+        match &**pattern {
+            Symbol(_) | Alias(_) => {
+                let literal = self.literals.iter().any(|l| same_identifier(l, pattern));
+                if literal || self.is_ellipsis(pattern) || pattern.identifier_name() == Some("_") {
+                    vec![]
+                } else {
+                    vec![pattern.clone()]
+                }
+            },
+            Pair(head, tail) => {
+                let mut vars = self.pattern_variables(head);
+                vars.extend(self.pattern_variables(tail));
+                vars
+            },
+            Vector(items) => items.borrow().iter().flat_map(|p| self.pattern_variables(p)).collect(),
+            _ => vec![],
+        }
+    }
+
+    fn transcribe(&self, template: &Expr, bindings: &Bindings, renames: &RefCell<Vec<(Expr, Expr)>>, escaped: bool) -> EvalResult<Expr> {
+        use Expression::*;
+
+        match &**template {
+            Symbol(_) | Alias(_) => match lookup(bindings, template) {
+                Some(Match::One(value)) => Ok(value.clone()),
+                Some(Match::Many(_)) => Err(bad_syntax("syntax-rules template", template)),
+                None => Ok(self.rename(template, renames)),
+            },
+            Pair(head, tail) => {
+                if !escaped && self.is_ellipsis(head) {
+                    return match tail.list_to_vec().as_deref() {
+                        Some([inner]) => self.transcribe(inner, bindings, renames, true),
+                        _ => Err(bad_syntax("syntax-rules template", template)),
+                    };
+                }
+
+                let (items, tail) = split_list(template);
+                let items = self.transcribe_sequence(&items, bindings, renames, escaped)?;
+                Ok(rebuild_list(&items, self.transcribe(&tail, bindings, renames, escaped)?))
+            },
+            Vector(items) => {
+                let items = self.transcribe_sequence(&items.borrow(), bindings, renames, escaped)?;
+                Ok(Rc::new(Vector(RefCell::new(items))))
+            },
+            _ => Ok(template.clone()),
+        }
+    }
+
+    fn transcribe_sequence(&self, templates: &[Expr], bindings: &Bindings, renames: &RefCell<Vec<(Expr, Expr)>>, escaped: bool) -> EvalResult<Vec<Expr>> {
+        let mut items = Vec::new();
+        let mut i = 0;
+
+        while i < templates.len() {
+            let template = &templates[i];
+            let mut depth = 0;
+            while !escaped && i + 1 + depth < templates.len() && self.is_ellipsis(&templates[i + 1 + depth]) {
+                depth += 1;
+            }
+
+            if depth == 0 {
+                items.push(self.transcribe(template, bindings, renames, escaped)?);
+            } else {
+                items.extend(self.transcribe_repeated(template, bindings, renames, depth)?);
+            }
+            i += 1 + depth;
+        }
+
+        Ok(items)
+    }
+
+    /// Transcribes a template followed by `depth` ellipses once for each repetition of the
+    /// pattern variables it contains.
+    fn transcribe_repeated(&self, template: &Expr, bindings: &Bindings, renames: &RefCell<Vec<(Expr, Expr)>>, depth: usize) -> EvalResult<Vec<Expr>> {
+        let repeated = self.pattern_variables(template).into_iter()
+            .filter_map(|var| match lookup(bindings, &var) {
+                Some(Match::Many(each)) => Some((var, each.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let count = match repeated.first() {
+            Some((_, each)) => each.len(),
+            None => return Err(bad_syntax("syntax-rules template", template)),
+        };
+        if repeated.iter().any(|(_, each)| each.len() != count) {
+            return Err(bad_syntax("syntax-rules template with mismatched repetition counts", template));
+        }
+
+        let mut items = Vec::new();
+        for i in 0..count {
+            let mut inner = bindings.clone();
+            for (var, each) in &repeated {
+                inner.retain(|(v, _)| !same_identifier(v, var));
+                inner.extend(each[i].iter().cloned());
+            }
+
+            if depth == 1 {
+                items.push(self.transcribe(template, &inner, renames, false)?);
+            } else {
+                items.extend(self.transcribe_repeated(template, &inner, renames, depth - 1)?);
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// Renames an identifier inserted by a template, giving every occurrence of the same
+    /// identifier within one expansion the same alias.
+    fn rename(&self, identifier: &Expr, renames: &RefCell<Vec<(Expr, Expr)>>) -> Expr {
+        let mut renames = renames.borrow_mut();
+
+        if let Some((_, alias)) = renames.iter().find(|(id, _)| same_identifier(id, identifier)) {
+            return alias.clone();
+        }
+
+        let alias = Rc::new(Expression::Alias(Rc::new(Alias { name: identifier.clone(), scope: self.scope.clone() })));
+        renames.push((identifier.clone(), alias.clone()));
+        alias
+    }
+}
+
+fn lookup<'a>(bindings: &'a Bindings, identifier: &Expr) -> Option<&'a Match> {
+    bindings.iter().rev().find(|(v, _)| same_identifier(v, identifier)).map(|(_, m)| m)
+}
+
+/// Splits a possibly improper list into its elements and final tail.
+fn split_list(expr: &Expr) -> (Vec<Expr>, Expr) {
+    let mut items = Vec::new();
+    let mut tail = expr;
+
+    while let Expression::Pair(head, rest) = &**tail {
+        items.push(head.clone());
+        tail = rest;
+    }
+
+    (items, tail.clone())
+}
+
+fn rebuild_list(items: &[Expr], tail: Expr) -> Expr {
+    items.iter().rev().fold(tail, |tail, head| Rc::new(Expression::Pair(head.clone(), tail)))
+}
@@ -4,7 +4,7 @@ use zed::Repl;
 
 fn main() {
     let mut repl = Repl::interactive();
-    if let Err(_) = repl.repl() {
+    if repl.repl().is_err() {
         process::exit(1);
     }
 }
\ No newline at end of file
@@ -1,7 +1,10 @@
 mod error;
 mod repl_printer;
+#[allow(clippy::module_inception)]
 mod print;
 
 pub use self::error::*;
 pub use self::repl_printer::*;
 pub use self::print::*;
+
+mod repl_printer_tests;
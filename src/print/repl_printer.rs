@@ -13,9 +13,12 @@ impl<T: Write> ReplPrinter<T> {
     }
 }
 
-impl<O: Write, T: Display> Print<T> for ReplPrinter<O> {
-    fn print(&mut self, value: T) -> PrintResult<()> {
-        writeln!(self.output, "---> {}", value)?;
+/// Prints each of the values of a result on its own line.
+impl<O: Write, T: IntoIterator> Print<T> for ReplPrinter<O> where T::Item: Display {
+    fn print(&mut self, values: T) -> PrintResult<()> {
+        for value in values {
+            writeln!(self.output, "---> {}", value)?;
+        }
         Ok(())
     }
 }
@@ -0,0 +1,23 @@
+#![cfg(test)]
+
+use std::rc::Rc;
+
+use test_case::test_case;
+
+use crate::data::{Expr, Expression, Values};
+use crate::print::{Print, ReplPrinter};
+
+fn symbol(name: &str) -> Expr {
+    Rc::new(Expression::Symbol(name.to_string()))
+}
+
+#[test_case(&[] => ""; "no values")]
+#[test_case(&["a"] => "---> a\n"; "one value")]
+#[test_case(&["a", "b"] => "---> a\n---> b\n"; "two values")]
+fn print_values(names: &[&str]) -> String {
+    let mut output = Vec::new();
+    let values = Values::new(names.iter().map(|name| symbol(name)).collect());
+
+    ReplPrinter::new(&mut output).print(values).unwrap();
+    String::from_utf8(output).unwrap()
+}
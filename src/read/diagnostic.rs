@@ -0,0 +1,200 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::rc::Rc;
+
+use peg::error::ParseError;
+use peg::str::LineCol;
+
+use crate::data::Source;
+use crate::read::open_parens;
+
+/// A parse error with enough context to point at the offending source text.
+#[derive(Debug)]
+pub struct ParseDiagnostic {
+    pub source: Rc<Source>,
+    pub offset: usize,
+    pub message: String,
+    pub help: Option<String>,
+}
+
+impl ParseDiagnostic {
+    pub (crate) fn new(source: &Rc<Source>, error: &ParseError<LineCol>) -> ParseDiagnostic {
+        let expected = error.expected.tokens().collect::<Vec<_>>();
+        let (offset, message, help) = explain(&source.text, error.location.offset, &expected);
+
+        ParseDiagnostic { source: source.clone(), offset, message, help }
+    }
+
+    pub fn line(&self) -> usize {
+        self.source.line_col(self.offset).0
+    }
+
+    pub fn column(&self) -> usize {
+        self.source.line_col(self.offset).1
+    }
+
+    /// Renders the message followed by the offending line with a caret under the error,
+    /// using ANSI colors if requested.
+    pub fn render(&self, color: bool) -> String {
+        let (bold, blue, red, reset) = if color {
+            ("\x1b[1m", "\x1b[1;34m", "\x1b[1;31m", "\x1b[0m")
+        } else {
+            ("", "", "", "")
+        };
+
+        let (line, column) = self.source.line_col(self.offset);
+        let text = self.source.text.lines().nth(line - 1).unwrap_or("");
+        let gutter = " ".repeat(line.to_string().len());
+        let padding = text.chars()
+            .take(column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+
+        let mut out = format!("{}{}{}\n", bold, self.message, reset);
+        out += &format!("{}{}-->{} {}:{}:{}\n", gutter, blue, reset, self.source.name, line, column);
+        out += &format!("{} {}|{}\n", gutter, blue, reset);
+        out += &format!("{}{} |{} {}\n", blue, line, reset, text);
+        out += &format!("{} {}|{} {}{}^{}", gutter, blue, reset, padding, red, reset);
+
+        if let Some(help) = &self.help {
+            out += &format!("\n{} {}={} help: {}", gutter, blue, reset, help);
+        }
+
+        out
+    }
+}
+
+impl Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.render(false))
+    }
+}
+
+impl Error for ParseDiagnostic {}
+
+fn explain(text: &str, offset: usize, expected: &[&str]) -> (usize, String, Option<String>) {
+    let found = text[offset..].chars().next();
+    let custom = expected.iter()
+        .filter(|t| t.chars().all(|c| c.is_ascii_lowercase() || c == ' '))
+        .copied()
+        .collect::<Vec<_>>();
+
+    if found.is_none() {
+        match open_parens(text) {
+            Ok(open) if !open.is_empty() => {
+                let help = "add a `)` to close this list".to_string();
+                return (*open.last().unwrap(), "missing `)`".to_string(), Some(help));
+            },
+            Err(start) => {
+                let (what, close) = match &text[start..start + 1] {
+                    "\"" => ("string", "\""),
+                    "|" => ("symbol", "|"),
+                    _ => ("block comment", "|#"),
+                };
+                let help = format!("close the {} with `{}`", what, close);
+                return (start, format!("unterminated {}", what), Some(help));
+            },
+            _ => {},
+        }
+    }
+
+    if custom.contains(&"invalid character") {
+        let start = text[..offset].rfind("#\\").unwrap_or(offset);
+        let help = "use a single character, a name such as `#\\space`, or a hex code such as `#\\x41`";
+        return (start, format!("unknown character `{}`", &text[start..offset]), Some(help.to_string()));
+    }
+
+    if let Some(start) = hex_escape_start(text, offset, expected) {
+        let help = "write `\\x` followed by hex digits and `;`, naming a Unicode scalar value, e.g. `\\x41;`";
+        return (start, "invalid hex escape".to_string(), Some(help.to_string()));
+    }
+
+    if let (Some(c), true) = (found, text[..offset].ends_with('\\')) {
+        let help = "valid escapes are `\\a`, `\\b`, `\\t`, `\\n`, `\\r`, `\\\\`, `\\\"`, `\\|` and `\\x<hex>;`";
+        return (offset - 1, format!("unknown escape `\\{}`", c), Some(help.to_string()));
+    }
+
+    if let Some((position, digit, radix)) = invalid_digit(text, offset) {
+        let name = match radix {
+            2 => "binary",
+            8 => "octal",
+            16 => "hexadecimal",
+            _ => "decimal",
+        };
+        return (position, format!("invalid digit `{}` in {} number", digit, name), None);
+    }
+
+    if !custom.is_empty() {
+        return (token_start(text, offset), custom.join(", "), None);
+    }
+
+    if found == Some(')') && !expected.contains(&"\")\"") {
+        return (offset, "unexpected `)` without a matching `(`".to_string(), None);
+    }
+
+    let message = match found {
+        Some(c) => format!("unexpected `{}`", c),
+        None => "unexpected end of input".to_string(),
+    };
+    let help = if expected.len() <= 8 {
+        Some(format!("expected one of {}", expected.join(", ")))
+    } else {
+        None
+    };
+
+    (offset, message, help)
+}
+
+/// Finds the start of a `\x` escape immediately before `offset` that failed to parse.
+fn hex_escape_start(text: &str, offset: usize, expected: &[&str]) -> Option<usize> {
+    let hex_expected = expected.iter().any(|t| t.contains("'a'..='f'"));
+    if !hex_expected && !expected.contains(&"invalid escape sequence") {
+        return None;
+    }
+
+    let start = text[..offset].rfind("\\x")?;
+    let digits = text[start + 2..offset].trim_end_matches(';');
+
+    if digits.chars().all(|c| c.is_ascii_hexdigit()) && !digits.contains(';') {
+        Some(start)
+    } else {
+        None
+    }
+}
+
+/// Finds the first character that is not a valid digit in a number with a radix prefix.
+fn invalid_digit(text: &str, offset: usize) -> Option<(usize, char, u32)> {
+    let mut start = token_start(text, offset);
+    let mut radix = None;
+
+    while let Some(prefix) = ["#b", "#o", "#d", "#x", "#e", "#i"].iter().find(|p| text[start..].starts_with(*p)) {
+        radix = match *prefix {
+            "#b" => Some(2),
+            "#o" => Some(8),
+            "#x" => Some(16),
+            "#d" => Some(10),
+            _ => radix.or(Some(10)),
+        };
+        start += 2;
+    }
+
+    let radix = radix?;
+    let end = text[start..].find(is_delimiter).map_or(text.len(), |i| start + i);
+    let body = &text[start..end];
+
+    if body.contains("inf.0") || body.contains("nan.0") {
+        return None;
+    }
+
+    body.char_indices()
+        .find(|&(_, c)| c.is_alphanumeric() && !c.is_digit(radix) && !(radix == 10 && c == 'e') && c != 'i')
+        .map(|(i, c)| (start + i, c, radix))
+}
+
+fn token_start(text: &str, offset: usize) -> usize {
+    text[..offset].rfind(is_delimiter).map_or(0, |i| i + 1)
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || "()\";|".contains(c)
+}
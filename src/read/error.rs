@@ -1,16 +1,16 @@
 use std::error::Error;
 use std::fmt::{Display, Formatter, Result as FmtResult};
-use std::io::{Error as IoError, ErrorKind};
+use std::io::Error as IoError;
 
-use peg::error::ParseError;
-use peg::str::LineCol;
 use rustyline::error::ReadlineError;
 
+use crate::read::ParseDiagnostic;
+
 #[derive(Debug)]
 pub enum ReadError {
     Eof,
     Io(IoError),
-    Parse(ParseError<LineCol>),
+    Parse(ParseDiagnostic),
 }
 
 pub type ReadResult<T> = Result<T, ReadError>;
@@ -45,8 +45,8 @@ impl From<IoError> for ReadError {
     }
 }
 
-impl From<ParseError<LineCol>> for ReadError {
-    fn from(err: ParseError<LineCol>) -> ReadError {
+impl From<ParseDiagnostic> for ReadError {
+    fn from(err: ParseDiagnostic) -> ReadError {
         ReadError::Parse(err)
     }
 }
@@ -58,7 +58,7 @@ impl From<ReadlineError> for ReadError {
         match err {
             Eof => ReadError::Eof,
             Io(e) => ReadError::Io(e),
-            _ => ReadError::Io(IoError::new(ErrorKind::Other, err)),
+            _ => ReadError::Io(IoError::other(err)),
         }
     }
 }
@@ -1,16 +1,22 @@
-use crate::data::Expr;
-use crate::read::{parse_exprs, Read, ReadResult};
+use crate::data::{Expr, Source};
+use crate::read::{parse_source, Read, ReadResult};
 
 pub struct ExprReader<T: Read<Output=String>> {
     input: T,
+    name: String,
     buffer: Vec<Expr>,
 }
 
 impl<T: Read<Output=String>> ExprReader<T> {
     pub fn new(input: T) -> Self {
+        ExprReader::with_name(input, "<input>")
+    }
+
+    /// Creates a reader whose expressions report `name` as their source in spans.
+    pub fn with_name<N: ToString>(input: T, name: N) -> Self {
         let buffer = Vec::new();
 
-        ExprReader { input, buffer }
+        ExprReader { input, name: name.to_string(), buffer }
     }
 }
 
@@ -24,7 +30,7 @@ impl<T: Read<Output=String>> Read for ExprReader<T> {
             }
 
             let text = self.input.read()?;
-            let exprs = parse_exprs(&text)?;
+            let exprs = parse_source(&Source::new(&self.name, text))?;
             self.buffer = exprs.into_iter().rev().collect();
         }
     }
@@ -2,18 +2,69 @@
 
 use test_case::test_case;
 
-use crate::data::Expr;
+use crate::data::{span_of, Expr, Expression};
 use crate::data::test_helpers::*;
 use crate::read::{ExprReader, Read, ReadError, ReadResult};
 
 #[test_case("()" => nil(); "nil value")]
+#[test_case("#t" => boolean(true); "short true")]
+#[test_case("#true" => boolean(true); "long true")]
+#[test_case("#f" => boolean(false); "short false")]
+#[test_case("#false" => boolean(false); "long false")]
+#[test_case("#\\a" => character('a'); "simple character")]
+#[test_case("#\\A" => character('A'); "uppercase character")]
+#[test_case("#\\(" => character('('); "delimiter character")]
+#[test_case("#\\ " => character(' '); "literal space character")]
+#[test_case("#\\space" => character(' '); "named space character")]
+#[test_case("#\\newline" => character('\n'); "named newline character")]
+#[test_case("#\\x" => character('x'); "x character")]
+#[test_case("#\\x41" => character('A'); "hex character")]
+#[test_case("#\\x3bb" => character('\u{3bb}'); "unicode hex character")]
 #[test_case("45" => number(integer(45)); "positive integer")]
 #[test_case("-9876" => number(integer(-9876)); "negative integer")]
+#[test_case("#x-ff" => number(integer(-255)); "hex integer")]
+#[test_case("#b101" => number(integer(5)); "binary integer")]
+#[test_case("1/3" => number(rational(1, 3)); "positive rational")]
+#[test_case("-22/7" => number(rational(-22, 7)); "negative rational")]
+#[test_case("+6/4" => number(rational(3, 2)); "unreduced rational")]
+#[test_case("8/4" => number(integer(2)); "integral rational")]
+#[test_case("#x-a/F" => number(rational(-2, 3)); "hex rational")]
+#[test_case("#b1/10" => number(rational(1, 2)); "binary rational")]
+#[test_case("1+2i" => number(exact_complex(integer(1), integer(2))); "rectangular complex")]
+#[test_case("-1.5-2.5i" => number(complex(-1.5, -2.5)); "negative rectangular complex")]
+#[test_case("1/2+i" => number(exact_complex(rational(1, 2), integer(1))); "rational complex")]
+#[test_case("+i" => number(exact_complex(integer(0), integer(1))); "positive imaginary unit")]
+#[test_case("-i" => number(exact_complex(integer(0), integer(-1))); "negative imaginary unit")]
+#[test_case("-2i" => number(exact_complex(integer(0), integer(-2))); "pure imaginary")]
+#[test_case("3+0i" => number(integer(3)); "exact zero imaginary part")]
+#[test_case("#x10+fi" => number(exact_complex(integer(16), integer(15))); "hex complex")]
+#[test_case("2@0" => number(integer(2)); "polar with zero angle")]
+#[test_case("#e1.5" => number(rational(3, 2)); "exact decimal")]
+#[test_case("#e1.5-2i" => number(exact_complex(rational(3, 2), integer(-2))); "exact decimal complex")]
+#[test_case("#i1+2i" => number(complex(1.0, 2.0)); "inexact complex")]
+#[test_case("#e1.1e-1" => number(rational(11, 100)); "exact decimal with exponent")]
+#[test_case("#e.5e3" => number(integer(500)); "exact decimal with positive exponent")]
+#[test_case("#i1/4" => number(real(0.25)); "inexact rational")]
+#[test_case("#i#x10" => number(real(16.0)); "inexact hex")]
+#[test_case("#x#e1A" => number(integer(26)); "hex then exact")]
+#[test_case("#e#x1A" => number(integer(26)); "exact then hex")]
+#[test_case("#e#b-101/10" => number(rational(-5, 2)); "exact binary rational")]
+#[test_case("#d#i3" => number(real(3.0)); "decimal then inexact")]
+#[test_case("+inf.0" => number(real(f64::INFINITY)); "positive infinity")]
+#[test_case("-inf.0" => number(real(f64::NEG_INFINITY)); "negative infinity")]
+#[test_case("1-inf.0i" => number(complex(1.0, f64::NEG_INFINITY)); "complex with infinite imaginary part")]
+#[test_case("+inf.0i" => number(complex(0.0, f64::INFINITY)); "infinite imaginary")]
 #[test_case("4.56" => number(real(4.56)); "positive real")]
 #[test_case("-87.0" => number(real(-87.0)); "negative real")]
 #[test_case("4e-2" => number(real(0.04)); "exponent real")]
 #[test_case("2." => number(real(2.0)); "left-handed real")]
 #[test_case(".92" => number(real(0.92)); "right-handed real")]
+#[test_case("\"\"" => string(""); "empty string")]
+#[test_case("\"hello world\"" => string("hello world"); "simple string")]
+#[test_case("\"a\\\"b\\\\c\\|d\"" => string("a\"b\\c|d"); "string with escaped delimiters")]
+#[test_case("\"tab\\tnew\\nhex\\x41;\"" => string("tab\tnew\nhexA"); "escaped string")]
+#[test_case("\"line\\   \n   continued\"" => string("linecontinued"); "string with line continuation")]
+#[test_case("\"multi\nline\"" => string("multi\nline"); "string with literal newline")]
 #[test_case("test-sym" => symbol("test-sym"); "basic symbol")]
 #[test_case("|quoted symbol|" => symbol("quoted symbol"); "quoted symbol")]
 #[test_case("|escaped\\nsymbol\\x45;|" => symbol("escaped\nsymbol\u{45}"); "escaped symbol")]
@@ -22,17 +73,81 @@ use crate::read::{ExprReader, Read, ReadError, ReadResult};
 #[test_case("(a . b)" => pair(symbol("a"), symbol("b")); "pair value")]
 #[test_case("(a b . c)" => pair(symbol("a"), pair(symbol("b"), symbol("c"))); "dotted list")]
 #[test_case("(a b c)" => list!(symbol("a"), symbol("b"), symbol("c")); "normal list")]
+#[test_case("(a ... .b)" => list!(symbol("a"), symbol("..."), symbol(".b")); "list of dot symbols")]
 #[test_case("(a (b) . ())" => list!(symbol("a"), list!(symbol("b"))); "complex list")]
+#[test_case("#()" => vector(vec![]); "empty vector")]
+#[test_case("#(1 (a) #(b))" => vector(vec![number(integer(1)), list!(symbol("a")), vector(vec![symbol("b")])]); "nested vector")]
+#[test_case("#u8()" => bytevector(vec![]); "empty bytevector")]
+#[test_case("#u8( 0 #xff 12 )" => bytevector(vec![0, 255, 12]); "bytevector value")]
+#[test_case("'a" => list!(symbol("quote"), symbol("a")); "quote shorthand")]
+#[test_case("' (a b)" => list!(symbol("quote"), list!(symbol("a"), symbol("b"))); "quote shorthand with space")]
+#[test_case("`(a ,b ,@c)" => list!(
+    symbol("quasiquote"),
+    list!(symbol("a"), list!(symbol("unquote"), symbol("b")), list!(symbol("unquote-splicing"), symbol("c"))),
+); "quasiquote shorthand")]
+#[test_case("''a" => list!(symbol("quote"), list!(symbol("quote"), symbol("a"))); "nested quote shorthand")]
+#[test_case("(#t #\\a)" => list!(boolean(true), character('a')); "literals in list")]
+#[test_case("(#f)" => list!(boolean(false)); "boolean before close paren")]
 fn parse_single_expr(text: &str) -> Expr {
     let input = MockTextReader::new(text);
     let mut reader = ExprReader::new(input);
 
     match reader.read() {
-        Ok(e) => return e,
+        Ok(e) => e,
         Err(e) => panic!("{}", e),
     }
 }
 
+#[test_case("#truth"; "boolean with trailing text")]
+#[test_case("\"unterminated"; "unterminated string")]
+#[test_case("\"bad \\q escape\""; "unknown string escape")]
+#[test_case("#\\spaces"; "unknown character name")]
+#[test_case("#| unterminated"; "unterminated block comment")]
+#[test_case("(a #;)"; "datum comment without datum")]
+#[test_case("1/0"; "zero denominator")]
+#[test_case("1/2/3"; "double rational")]
+#[test_case("#b102"; "invalid binary digit")]
+#[test_case("1+2"; "complex without i")]
+#[test_case("1+2j"; "complex with wrong suffix")]
+#[test_case("#x1.5"; "hex decimal")]
+#[test_case("#e+inf.0"; "exact infinity")]
+#[test_case("#e#e1"; "repeated exactness prefix")]
+#[test_case("#x#x1"; "repeated radix prefix")]
+#[test_case("#e1.5e-2147483648"; "exact decimal scale overflow")]
+#[test_case("#e1e999999999"; "exact decimal exponent too large")]
+#[test_case("#u8(256)"; "byte out of range")]
+#[test_case("#u8(1.5)"; "non-integer byte")]
+#[test_case("#u8(a)"; "symbol in bytevector")]
+#[test_case("#\\x110000"; "out of range hex character")]
+fn parse_invalid_expr(text: &str) {
+    let input = MockTextReader::new(text);
+    let mut reader = ExprReader::new(input);
+
+    assert!(matches!(reader.read(), Err(ReadError::Parse(_))));
+}
+
+#[test_case("\"a\\x3bb;\\\"\\\\\\n\\x7;\"" => vec!["\"a\u{3bb}\\\"\\\\\\n\\a\""]; "string round trip")]
+#[test_case("+nan.0 -nan.0 +inf.0 -inf.0 1e300 -0.0 0.1 +nan.0i" => vec!["+nan.0", "+nan.0", "+inf.0", "-inf.0", "1e300", "-0.0", "0.1", "0.0+nan.0i"]; "special reals round trip")]
+#[test_case("#e0.1 #i1/3 #x#iF" => vec!["1/10", "0.3333333333333333", "15.0"]; "exactness round trip")]
+#[test_case("1+2i 1/2-i #e1.5+2i #i1+2i #x-a+bi" => vec!["1+2i", "1/2-1i", "3/2+2i", "1.0+2.0i", "-10+11i"]; "complex round trip")]
+#[test_case("+inf.1 +info" => vec!["+inf.1", "+info"]; "infinity lookalike identifiers")]
+#[test_case("+ - ... +a -i +i2" => vec!["+", "-", "...", "+a", "0-1i", "+i2"]; "signs and imaginary units")]
+#[test_case("'a `(a ,b ,@(c d)) (quote a b) (quote . a)" => vec!["'a", "`(a ,b ,@(c d))", "(quote a b)", "(quote . a)"]; "abbreviations round trip")]
+#[test_case("#t #\\x41 #\\space #\\x3bb #\\x7f" => vec!["#t", "#\\A", "#\\space", "#\\\u{3bb}", "#\\delete"]; "literals round trip")]
+fn parse_and_format(text: &str) -> Vec<String> {
+    let input = MockTextReader::new(text);
+    let mut reader = ExprReader::new(input);
+
+    let mut items = Vec::new();
+    loop {
+        match reader.read() {
+            Ok(e) => items.push(e.to_string()),
+            Err(ReadError::Eof) => return items,
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
 #[test_case("(+ 2 3) equals 5" => vec![
     list!(symbol("+"), number(integer(2)), number(integer(3))),
     symbol("equals"),
@@ -44,6 +159,13 @@ fn parse_single_expr(text: &str) -> Expr {
     symbol("e"),
     symbol("f"),
 ]; "values with comment")]
+#[test_case("a #| b (c |# d" => vec![symbol("a"), symbol("d")]; "block comment")]
+#[test_case("a #| b #| c |# d |# e" => vec![symbol("a"), symbol("e")]; "nested block comment")]
+#[test_case("(a#|x|#b)" => vec![list!(symbol("a"), symbol("b"))]; "block comment between items")]
+#[test_case("a #;(b c) d" => vec![symbol("a"), symbol("d")]; "datum comment")]
+#[test_case("(a #; b)" => vec![list!(symbol("a"))]; "datum comment at end of list")]
+#[test_case("#;#;a b c" => vec![symbol("c")]; "nested datum comments")]
+#[test_case("#; #| x |# a b" => vec![symbol("b")]; "datum comment over block comment")]
 fn parse_multiple_expr(text: &str) -> Vec<Expr> {
     let input = MockTextReader::new(text);
     let mut reader = ExprReader::new(input);
@@ -58,6 +180,96 @@ fn parse_multiple_expr(text: &str) -> Vec<Expr> {
     }
 }
 
+#[test_case("(a\n  b)" => vec![
+    ("(a\n  b)".to_string(), "test:1:1".to_string()),
+    ("a".to_string(), "test:1:2".to_string()),
+    ("b".to_string(), "test:2:3".to_string()),
+]; "list spanning lines")]
+#[test_case("  'x #;y \"λ\" z" => vec![
+    ("'x".to_string(), "test:1:3".to_string()),
+    ("x".to_string(), "test:1:4".to_string()),
+    ("\"λ\"".to_string(), "test:1:10".to_string()),
+    ("z".to_string(), "test:1:14".to_string()),
+]; "abbreviation, comment and unicode")]
+#[test_case("#(1 #u8(2))" => vec![
+    ("#(1 #u8(2))".to_string(), "test:1:1".to_string()),
+    ("1".to_string(), "test:1:3".to_string()),
+    ("#u8(2)".to_string(), "test:1:5".to_string()),
+]; "vector items")]
+fn parse_spans(text: &str) -> Vec<(String, String)> {
+    fn collect(expr: &Expr, spans: &mut Vec<(String, String)>) {
+        if let Some(span) = span_of(expr) {
+            spans.push((span.text().to_string(), span.to_string()));
+        }
+
+        match &**expr {
+            Expression::Pair(head, tail) => {
+                collect(head, spans);
+                if span_of(tail).is_none() {
+                    collect(tail, spans);
+                }
+            },
+            Expression::Vector(items) => items.borrow().iter().for_each(|item| collect(item, spans)),
+            _ => {},
+        }
+    }
+
+    let input = MockTextReader::new(text);
+    let mut reader = ExprReader::with_name(input, "test");
+
+    let mut spans = Vec::new();
+    loop {
+        match reader.read() {
+            Ok(e) => collect(&e, &mut spans),
+            Err(ReadError::Eof) => return spans,
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+#[test_case("(a))" => ("unexpected `)` without a matching `(`".to_string(), 1, 4); "unbalanced close paren")]
+#[test_case("(a\n (b)" => ("missing `)`".to_string(), 1, 1); "unclosed list")]
+#[test_case("(a \"bc" => ("unterminated string".to_string(), 1, 4); "unterminated string")]
+#[test_case("#| a" => ("unterminated block comment".to_string(), 1, 1); "unterminated comment")]
+#[test_case("\"ab\\xZZ;\"" => ("invalid hex escape".to_string(), 1, 4); "non-hex escape")]
+#[test_case("(f \"\\x110000;\")" => ("invalid hex escape".to_string(), 1, 5); "out of range escape")]
+#[test_case("\"bad \\q\"" => ("unknown escape `\\q`".to_string(), 1, 6); "unknown escape")]
+#[test_case("(+ 1\n   #b102)" => ("invalid digit `2` in binary number".to_string(), 2, 8); "invalid binary digit")]
+#[test_case("#e#o78" => ("invalid digit `8` in octal number".to_string(), 1, 6); "invalid octal digit")]
+#[test_case("#xAG" => ("invalid digit `G` in hexadecimal number".to_string(), 1, 4); "invalid hex digit")]
+#[test_case("(#\\spaces)" => ("unknown character `#\\spaces`".to_string(), 1, 2); "unknown character name")]
+#[test_case("#u8(1 256)" => ("invalid byte".to_string(), 1, 7); "byte out of range")]
+fn parse_diagnostic(text: &str) -> (String, usize, usize) {
+    let input = MockTextReader::new(text);
+    let mut reader = ExprReader::new(input);
+
+    match reader.read() {
+        Err(ReadError::Parse(d)) => (d.message.clone(), d.line(), d.column()),
+        other => panic!("expected parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn render_diagnostic() {
+    let input = MockTextReader::new("(display\n  \"x\\q\")");
+    let mut reader = ExprReader::with_name(input, "test.scm");
+
+    let diagnostic = match reader.read() {
+        Err(ReadError::Parse(d)) => d,
+        other => panic!("expected parse error, got {:?}", other),
+    };
+
+    assert_eq!(diagnostic.render(false), [
+        "unknown escape `\\q`",
+        " --> test.scm:2:5",
+        "  |",
+        "2 |   \"x\\q\")",
+        "  |     ^",
+        "  = help: valid escapes are `\\a`, `\\b`, `\\t`, `\\n`, `\\r`, `\\\\`, `\\\"`, `\\|` and `\\x<hex>;`",
+    ].join("\n"));
+    assert!(diagnostic.render(true).contains("\x1b[1;31m^\x1b[0m"));
+}
+
 struct MockTextReader {
     text: Option<String>,
 }
@@ -1,9 +1,12 @@
+mod diagnostic;
 mod error;
 mod expr_reader;
 mod parser;
+#[allow(clippy::module_inception)]
 mod read;
 mod repl_text_reader;
 
+pub use self::diagnostic::*;
 pub use self::error::*;
 pub use self::expr_reader::*;
 pub (crate) use self::parser::*;
@@ -11,3 +14,4 @@ pub use self::read::*;
 pub use self::repl_text_reader::*;
 
 mod expr_reader_tests;
+mod repl_text_reader_tests;
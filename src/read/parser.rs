@@ -1,44 +1,114 @@
+use std::cell::RefCell;
 use std::convert::TryFrom;
 use std::rc::Rc;
 
-use num::{BigInt, Num};
+use num::{BigInt, BigRational, Num, One, ToPrimitive, Zero};
 
-use crate::data::{Expr, Expression, Number};
-use crate::read::ReadResult;
+use crate::data::{prune_spans, set_span, Expr, Expression, Number, Source, Span};
+use crate::read::{ParseDiagnostic, ReadResult};
 
-pub (crate) fn parse_exprs(text: &str) -> ReadResult<Vec<Expr>> {
-    Ok(zed::exprs(text)?)
+/// Parses all the expressions in a source, recording the span of each datum.
+pub (crate) fn parse_source(source: &Rc<Source>) -> ReadResult<Vec<Expr>> {
+    prune_spans();
+    zed::exprs(&source.text, source).map_err(|err| ParseDiagnostic::new(source, &err).into())
+}
+
+pub (crate) fn read_number(text: &str, radix: u32) -> Option<Number> {
+    zed::number(text, &Source::new("<string>", text), radix).ok()
 }
 
 pub (crate) fn is_simple_identifier(text: &str) -> bool {
-    zed::simple_identifier(text).is_ok()
+    zed::simple_identifier(text, &Source::new("<string>", text)).is_ok()
 }
 
 peg::parser! {
-    grammar zed() for str {
+    grammar zed(source: &Rc<Source>) for str {
         pub rule exprs() -> Vec<Expr>
             = _* es:expr()**(_*) _*     { es }
         pub rule simple_identifier() = initial() subsequent()* / peculiar_identifier()
 
         rule expr() -> Expr
-            = n:number()        { Rc::new(Expression::Number(n)) }
-            / i:identifier()    { Rc::new(Expression::Symbol(i)) }
+            = start:position!() e:datum() end:position!() {
+                set_span(&e, Span { source: source.clone(), start, end });
+                e
+            }
+        rule datum() -> Expr
+            = b:boolean()              { Rc::new(Expression::Boolean(b)) }
+            / c:character()            { Rc::new(Expression::Char(c)) }
+            / s:string()               { Rc::new(Expression::String(RefCell::new(s))) }
+            / n:number(10) &delimiter()  { Rc::new(Expression::Number(n)) }
+            / i:identifier()           { Rc::new(Expression::Symbol(i)) }
             / list()
+            / vector()
+            / bytevector()
+            / abbreviation()
 
-        rule number() -> Number
-            = r:real()      { Number::Real(r) }
-            / i:integer()   { Number::Integer(i) }
+        pub rule number(default_radix: u32) -> Number
+            = e:exactness() r:radix(default_radix) n:complex(r, e)      {? apply_exactness(n, e) }
+            / r:radix(default_radix) e:exactness() n:complex(r, e)      {? apply_exactness(n, e) }
+        rule radix(default_radix: u32) -> u32
+            = "#b"      { 2 }
+            / "#o"      { 8 }
+            / "#x"      { 16 }
+            / "#d"      { 10 }
+            / ""        { default_radix }
+        rule exactness() -> Exactness
+            = "#e"      { Exactness::Exact }
+            / "#i"      { Exactness::Inexact }
+            / ""        { Exactness::Unspecified }
+        rule complex(radix: u32, e: Exactness) -> Number
+            = m:real(radix, e) "@" a:real(radix, e)         { Number::polar(&m, &a) }
+            / r:real(radix, e) i:imaginary(radix, e)        { Number::rectangular(&r, &i) }
+            / r:real(radix, e) !"i"                         { r }
+            / i:imaginary(radix, e)                         { Number::rectangular(&Number::Integer(BigInt::zero()), &i) }
+        rule imaginary(radix: u32, e: Exactness) -> Number
+            = n:infnan() "i"                { n }
+            / "+" n:ureal(radix, e)? "i"    { n.unwrap_or_else(|| Number::Integer(BigInt::one())) }
+            / "-" n:ureal(radix, e)? "i"    { n.unwrap_or_else(|| Number::Integer(BigInt::one())).neg() }
+        rule real(radix: u32, e: Exactness) -> Number
+            = infnan()
+            / "+"? n:ureal(radix, e)    { n }
+            / "-" n:ureal(radix, e)     { n.neg() }
+        rule infnan() -> Number
+            = "+inf.0"      { Number::Real(f64::INFINITY) }
+            / "-inf.0"      { Number::Real(f64::NEG_INFINITY) }
+            / "+nan.0"      { Number::Real(f64::NAN) }
+            / "-nan.0"      { Number::Real(-f64::NAN) }
+        rule ureal(radix: u32, e: Exactness) -> Number
+            = s:$(digits(radix) "/" digits(radix))      {? parse_rational(s, radix) }
+            / s:$(decimal())                            {? parse_decimal(s, radix, e) }
+            / i:uinteger(radix)                         { Number::Integer(i) }
+        rule uinteger(radix: u32) -> BigInt
+            = s:$(digits(radix))    {? parse_number(s, radix, "invalid integer") }
+        rule digits(radix: u32) = [c if c.is_digit(radix)]+
         rule integer() -> BigInt
-            = "#b" s:$(explicit_sign()? ['0'|'1']+)     {? parse_number(s, 2, "invalid integer") }
-            / "#o" s:$(explicit_sign()? ['0'..='7']+)   {? parse_number(s, 8, "invalid integer") }
-            / "#x" s:$(explicit_sign()? hex_digit()+)   {? parse_number(s, 16, "invalid integer") }
-            / "#d"? s:$(explicit_sign()? digit()+)      {? parse_number(s, 10, "invalid integer") }
-        rule real() -> f64
-            = s:$(explicit_sign()? digit()+ exponent())                 {? parse_number(s, 10, "invalid real") }
-            / s:$(explicit_sign()? digit()+ "." digit()* exponent()?)   {? parse_number(s, 10, "invalid real") }
-            / s:$(explicit_sign()? "." digit()+ exponent()?)            {? parse_number(s, 10, "invalid real") }
+            = r:radix(10) s:$(explicit_sign()? digits(r))     {? parse_number(s, r, "invalid integer") }
+        rule decimal()
+            = digit()+ exponent()
+            / digit()+ "." digit()* exponent()?
+            / "." digit()+ exponent()?
         rule exponent() = "e" explicit_sign()? digit()+
 
+        rule boolean() -> bool
+            = ("#true" / "#t") &delimiter()     { true }
+            / ("#false" / "#f") &delimiter()    { false }
+
+        rule character() -> char
+            = "#\\" s:$([_] (!delimiter() [_])*)  {? parse_character(s) }
+
+        rule string() -> String
+            = "\"" cs:string_element()* "\""     { cs.into_iter().flatten().collect() }
+        rule string_element() -> Option<char>
+            = !['\\'|'"'] c:$([_])       { c.chars().next() }
+            / c:inline_hex_escape()     { Some(c) }
+            / c:mnemonic_escape()       { Some(c) }
+            / "\\\""                    { Some('"') }
+            / "\\|"                     { Some('|') }
+            / line_continuation()       { None }
+        rule line_continuation()
+            = "\\" intraline_whitespace()* ("\r\n" / ['\r'|'\n']) intraline_whitespace()*
+        rule intraline_whitespace() = [' '|'\t']
+
         rule identifier() -> String
             = s:$(initial() subsequent()*)      { s.to_string() }
             / "|" cs:symbol_element()* "|"      { cs.into_iter().collect() }
@@ -59,6 +129,7 @@ peg::parser! {
             / "\\t"     { '\t' }
             / "\\n"     { '\n' }
             / "\\r"     { '\r' }
+            / "\\\\"    { '\\' }
         rule peculiar_identifier()
             = explicit_sign() (sign_subsequent() subsequent()*)?
             / explicit_sign() "." dot_subsequent() subsequent()*
@@ -71,16 +142,40 @@ peg::parser! {
             / mnemonic_escape()
             / "\\|"                     { '|' }
 
+        rule abbreviation() -> Expr
+            = p:abbrev_prefix() _* e:expr() {
+                let tail = Rc::new(Expression::Pair(e, Rc::new(Expression::Nil)));
+                Rc::new(Expression::Pair(Rc::new(Expression::Symbol(p.to_string())), tail))
+            }
+        rule abbrev_prefix() -> &'static str
+            = "'"       { "quote" }
+            / "`"       { "quasiquote" }
+            / ",@"      { "unquote-splicing" }
+            / ","       { "unquote" }
+
         rule list() -> Expr
             = "(" _* l:list_internal()      { l }
         rule list_internal() -> Expr
             = ")"                                   { Rc::new(Expression::Nil) }
-            / e1:expr() _* "." _* e2:expr() _* ")"  { Rc::new(Expression::Pair(e1, e2)) }
+            / e1:expr() _* "." &delimiter() _* e2:expr() _* ")"  { Rc::new(Expression::Pair(e1, e2)) }
             / e:expr() _* l:list_internal()         { Rc::new(Expression::Pair(e, l)) }
 
+        rule vector() -> Expr
+            = "#(" _* es:expr()**(_*) _* ")"       { Rc::new(Expression::Vector(RefCell::new(es))) }
+        rule bytevector() -> Expr
+            = "#u8(" _* bs:byte()**(_*) _* ")"     { Rc::new(Expression::Bytevector(RefCell::new(bs))) }
+        rule byte() -> u8
+            = i:integer() &delimiter()      {? i.to_u8().ok_or("invalid byte") }
+
+        rule delimiter() = [' '|'\t'|'\r'|'\n'|'|'|'('|')'|'"'|';'] / ![_]
+
         rule _()
             = [' '|'\t'|'\r'|'\n']
             / ";" (!['\r'|'\n'] [_])* (['\r'|'\n'] / ![_])
+            / block_comment()
+            / "#;" _* expr()
+        rule block_comment()
+            = "#|" (block_comment() / !"|#" [_])* "|#"
     }
 }
 
@@ -88,6 +183,95 @@ fn parse_number<T: Num>(text: &str, radix: u32, msg: &'static str) -> Result<T,
     T::from_str_radix(text, radix).map_err(|_| msg)
 }
 
+pub (crate) const CHAR_NAMES: [(&str, char); 9] = [
+    ("alarm", '\u{0007}'),
+    ("backspace", '\u{0008}'),
+    ("delete", '\u{007f}'),
+    ("escape", '\u{001b}'),
+    ("newline", '\n'),
+    ("null", '\u{0000}'),
+    ("return", '\r'),
+    ("space", ' '),
+    ("tab", '\t'),
+];
+
+fn parse_character(text: &str) -> Result<char, &'static str> {
+    let mut chars = text.chars();
+    let first = chars.next().unwrap();
+
+    if chars.as_str().is_empty() {
+        return Ok(first);
+    }
+
+    if first == 'x' {
+        return parse_hex_escape(chars.as_str()).map_err(|_| "invalid character");
+    }
+
+    CHAR_NAMES.iter()
+        .find(|(name, _)| *name == text)
+        .map(|(_, ch)| *ch)
+        .ok_or("invalid character")
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Exactness {
+    Exact,
+    Inexact,
+    Unspecified,
+}
+
+fn apply_exactness(number: Number, exactness: Exactness) -> Result<Number, &'static str> {
+    match exactness {
+        Exactness::Exact => number.to_exact().ok_or("no exact representation"),
+        Exactness::Inexact => Ok(number.to_inexact()),
+        Exactness::Unspecified => Ok(number),
+    }
+}
+
+/// The largest power of ten an exact decimal may be scaled by, since the scale is computed
+/// exactly.
+const MAX_EXACT_EXPONENT: i32 = 100_000;
+
+fn parse_decimal(text: &str, radix: u32, exactness: Exactness) -> Result<Number, &'static str> {
+    if radix != 10 {
+        return Err("decimal point outside radix 10");
+    }
+
+    if exactness != Exactness::Exact {
+        return parse_number(text, radix, "invalid real").map(Number::Real);
+    }
+
+    let (mantissa, exponent) = match text.find('e') {
+        Some(i) => (&text[..i], text[i + 1..].parse::<i32>().map_err(|_| "invalid real")?),
+        None => (text, 0),
+    };
+    let (whole, fraction) = match mantissa.find('.') {
+        Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+        None => (mantissa, ""),
+    };
+
+    let digits: BigInt = parse_number(&format!("0{}{}", whole, fraction), 10, "invalid real")?;
+    let scale = i32::try_from(fraction.len()).ok()
+        .and_then(|length| exponent.checked_sub(length))
+        .filter(|scale| scale.abs() <= MAX_EXACT_EXPONENT)
+        .ok_or("exponent too large for an exact number")?;
+    let ten = BigRational::from_integer(BigInt::from(10));
+
+    Ok(Number::rational(BigRational::from_integer(digits) * ten.pow(scale)))
+}
+
+fn parse_rational(text: &str, radix: u32) -> Result<Number, &'static str> {
+    let (numer, denom) = text.split_at(text.find('/').unwrap());
+    let numer: BigInt = parse_number(numer, radix, "invalid rational")?;
+    let denom: BigInt = parse_number(&denom[1..], radix, "invalid rational")?;
+
+    if denom.is_zero() {
+        return Err("invalid rational");
+    }
+
+    Ok(Number::rational(BigRational::new(numer, denom)))
+}
+
 fn parse_hex_escape(escape: &str) -> Result<char, &'static str> {
     if let Ok(n) = u32::from_str_radix(escape, 16) {
         if let Ok(ch) = char::try_from(n) {
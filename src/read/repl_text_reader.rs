@@ -1,3 +1,6 @@
+use std::iter::Peekable;
+use std::str::CharIndices;
+
 use rustyline::Editor;
 
 use crate::read::{Read, ReadResult};
@@ -14,24 +17,16 @@ impl ReplTextReader {
     }
 }
 
+impl Default for ReplTextReader {
+    fn default() -> Self {
+        ReplTextReader::new()
+    }
+}
+
 impl Read for ReplTextReader {
     type Output = String;
 
     fn read(&mut self) -> ReadResult<String> {
-        fn match_braces(text: &str) -> bool {
-            let mut level = 0;
-
-            for ch in text.chars() {
-                if ch == '(' {
-                    level += 1;
-                } else if ch == ')' {
-                    level -= 1;
-                }
-            }
-
-            level <= 0
-        }
-
         let mut text = self.editor.readline("> ")?;
 
         while !match_braces(&text) {
@@ -44,3 +39,88 @@ impl Read for ReplTextReader {
         Ok(text)
     }
 }
+
+pub (crate) fn match_braces(text: &str) -> bool {
+    open_parens(text).is_ok_and(|open| open.is_empty())
+}
+
+/// Scans for parentheses that have not been closed, returning their byte offsets. If the text
+/// ends inside a string, quoted symbol or block comment, returns the offset where it started
+/// instead. Extra closing parentheses are ignored.
+pub (crate) fn open_parens(text: &str) -> Result<Vec<usize>, usize> {
+    fn skip_delimited(chars: &mut Peekable<CharIndices>, delimiter: char) -> bool {
+        while let Some((_, ch)) = chars.next() {
+            if ch == delimiter {
+                return true;
+            } else if ch == '\\' {
+                chars.next();
+            }
+        }
+
+        false
+    }
+
+    fn skip_block_comment(chars: &mut Peekable<CharIndices>) -> bool {
+        let mut depth = 1;
+
+        while let Some((_, ch)) = chars.next() {
+            match (ch, chars.peek()) {
+                ('|', Some((_, '#'))) => {
+                    chars.next();
+                    depth -= 1;
+
+                    if depth == 0 {
+                        return true;
+                    }
+                },
+                ('#', Some((_, '|'))) => {
+                    chars.next();
+                    depth += 1;
+                },
+                _ => {},
+            }
+        }
+
+        false
+    }
+
+    let mut open = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, ch)) = chars.next() {
+        let complete = match ch {
+            '(' => {
+                open.push(i);
+                true
+            },
+            ')' => {
+                open.pop();
+                true
+            },
+            ';' => {
+                while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+                true
+            },
+            '"' | '|' => skip_delimited(&mut chars, ch),
+            '#' => match chars.peek() {
+                Some((_, '\\')) => {
+                    chars.next();
+                    chars.next();
+                    true
+                },
+                Some((_, '|')) => {
+                    chars.next();
+                    skip_block_comment(&mut chars)
+                },
+                _ => true,
+            },
+            _ => true,
+        };
+
+        if !complete {
+            return Err(i);
+        }
+    }
+
+    Ok(open)
+}
@@ -0,0 +1,23 @@
+#![cfg(test)]
+
+use test_case::test_case;
+
+use crate::read::match_braces;
+
+#[test_case("(+ 1 2)" => true; "balanced list")]
+#[test_case("(+ 1" => false; "open list")]
+#[test_case("(a))" => true; "extra close paren")]
+#[test_case("(a ; (b\n)" => true; "paren in line comment")]
+#[test_case("(a #| ) |# b" => false; "paren in block comment")]
+#[test_case("#| outer #| inner |# ( |# x" => true; "nested block comment")]
+#[test_case("#| outer #| inner |#" => false; "unterminated block comment")]
+#[test_case("#;(a b) c" => true; "datum comment")]
+#[test_case("(display \")\"" => false; "paren in string")]
+#[test_case("\"unterminated" => false; "unterminated string")]
+#[test_case("\"escaped \\\" (\"" => true; "escaped quote in string")]
+#[test_case("|odd ( symbol|" => true; "paren in quoted symbol")]
+#[test_case("(list #\\( #\\))" => true; "paren characters")]
+#[test_case("#(1 2)" => true; "vector")]
+fn braces(text: &str) -> bool {
+    match_braces(text)
+}
@@ -1,4 +1,4 @@
-use std::io::{stdout, Stdout};
+use std::io::{stderr, stdout, IsTerminal, Stdout};
 
 use crate::{ZedError, ZedResult};
 use crate::eval::{Eval, ExprEvaluator};
@@ -9,15 +9,32 @@ pub struct Repl<R: Read, E: Eval<<R as Read>::Output>, P: Print<<E as Eval<<R as
     reader: R,
     evaluator: E,
     printer: P,
+    colors: bool,
 }
 
 impl<R: Read, E: Eval<<R as Read>::Output>, P: Print<<E as Eval<<R as Read>::Output>>::Output>> Repl<R, E, P> {
     pub fn new(reader: R, evaluator: E, printer: P) -> Self {
-        Repl { reader, evaluator, printer }
+        Repl { reader, evaluator, printer, colors: false }
+    }
+
+    /// Enables ANSI colors in error diagnostics.
+    pub fn with_colors(mut self, colors: bool) -> Self {
+        self.colors = colors;
+        self
     }
 
     pub fn rep(&mut self) -> ZedResult<()> {
-        self.printer.print(self.evaluator.eval(self.reader.read()?)?)?;
+        let value = self.reader.read()?;
+
+        let output = match self.evaluator.command(&value) {
+            Some(name) => {
+                let operand = self.reader.read()?;
+                self.evaluator.run_command(&name, operand)?
+            },
+            None => self.evaluator.eval(value)?,
+        };
+
+        self.printer.print(output)?;
         Ok(())
     }
 
@@ -29,7 +46,11 @@ impl<R: Read, E: Eval<<R as Read>::Output>, P: Print<<E as Eval<<R as Read>::Out
             if let Err(err) = self.rep() {
                 match err {
                     Read(Eof) => return Ok(()),
-                    Read(Parse(_)) | Eval(_) => {
+                    Read(Parse(diagnostic)) => {
+                        self.reader.clear_buffer();
+                        eprintln!("ERROR: parse error: {}", diagnostic.render(self.colors));
+                    },
+                    Eval(_) => {
                         self.reader.clear_buffer();
                         eprintln!("ERROR: {}", err);
                     },
@@ -46,9 +67,10 @@ impl<R: Read, E: Eval<<R as Read>::Output>, P: Print<<E as Eval<<R as Read>::Out
 impl Repl<ExprReader<ReplTextReader>, ExprEvaluator, ReplPrinter<Stdout>> {
     pub fn interactive() -> Self {
         Repl {
-            reader: ExprReader::new(ReplTextReader::new()),
-            evaluator: ExprEvaluator,
+            reader: ExprReader::with_name(ReplTextReader::new(), "<repl>"),
+            evaluator: ExprEvaluator::new(),
             printer: ReplPrinter::new(stdout()),
+            colors: stderr().is_terminal(),
         }
     }
 }